@@ -0,0 +1,5 @@
+CREATE TABLE guild_settings (
+    guild_id VARCHAR(255) PRIMARY KEY,
+    max_queue_length INT NOT NULL DEFAULT 5,
+    queue_drop_policy VARCHAR(32) NOT NULL DEFAULT 'newest'
+)
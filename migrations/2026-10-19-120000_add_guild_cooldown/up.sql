@@ -0,0 +1,2 @@
+ALTER TABLE guild_settings
+ADD COLUMN cooldown_seconds INT NOT NULL DEFAULT 30;
@@ -0,0 +1,7 @@
+CREATE TABLE member_cooldowns (
+    guild_id VARCHAR(255) NOT NULL,
+    discord_id VARCHAR(255) NOT NULL,
+    cooldown_seconds INT,
+    last_triggered TIMESTAMP NULL,
+    PRIMARY KEY (guild_id, discord_id)
+)
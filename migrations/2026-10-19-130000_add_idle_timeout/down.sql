@@ -0,0 +1,3 @@
+ALTER TABLE guild_settings
+DROP COLUMN idle_timeout_minutes,
+DROP COLUMN stay_while_occupied;
@@ -0,0 +1,3 @@
+ALTER TABLE guild_settings
+ADD COLUMN idle_timeout_minutes INT NOT NULL DEFAULT 0,
+ADD COLUMN stay_while_occupied BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,3 @@
+ALTER TABLE guild_settings
+DROP COLUMN play_on_join,
+DROP COLUMN move_policy;
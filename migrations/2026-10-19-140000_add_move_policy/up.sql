@@ -0,0 +1,3 @@
+ALTER TABLE guild_settings
+ADD COLUMN play_on_join BOOLEAN NOT NULL DEFAULT TRUE,
+ADD COLUMN move_policy VARCHAR(32) NOT NULL DEFAULT 'never';
@@ -0,0 +1,7 @@
+CREATE TABLE move_triggers (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    guild_id VARCHAR(255) NOT NULL,
+    from_channel_id VARCHAR(255),
+    to_channel_id VARCHAR(255),
+    INDEX move_triggers_guild_idx (guild_id)
+)
@@ -0,0 +1,2 @@
+ALTER TABLE joinsounds
+DROP COLUMN kind;
@@ -0,0 +1,2 @@
+ALTER TABLE joinsounds
+ADD COLUMN kind VARCHAR(32) NOT NULL DEFAULT 'join';
@@ -0,0 +1,2 @@
+ALTER TABLE guild_settings
+ADD COLUMN busy_policy VARCHAR(32) NOT NULL DEFAULT 'queue';
@@ -0,0 +1,5 @@
+ALTER TABLE guild_settings
+DROP COLUMN ignore_afk_channel,
+DROP COLUMN ignore_deafened,
+DROP COLUMN ignore_muted,
+DROP COLUMN min_other_members;
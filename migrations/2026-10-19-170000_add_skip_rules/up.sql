@@ -0,0 +1,5 @@
+ALTER TABLE guild_settings
+ADD COLUMN ignore_afk_channel BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN ignore_deafened BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN ignore_muted BOOLEAN NOT NULL DEFAULT FALSE,
+ADD COLUMN min_other_members INT NOT NULL DEFAULT 0;
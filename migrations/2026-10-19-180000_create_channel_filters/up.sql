@@ -0,0 +1,7 @@
+CREATE TABLE channel_filters (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    guild_id VARCHAR(255) NOT NULL,
+    channel_id VARCHAR(255) NOT NULL,
+    list VARCHAR(32) NOT NULL,
+    INDEX channel_filters_guild_idx (guild_id)
+)
@@ -0,0 +1,2 @@
+ALTER TABLE guild_settings
+ADD COLUMN timezone VARCHAR(64) NOT NULL DEFAULT 'UTC';
@@ -0,0 +1,7 @@
+CREATE TABLE quiet_hours (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    guild_id VARCHAR(255) NOT NULL,
+    start_time TIME NOT NULL,
+    end_time TIME NOT NULL,
+    INDEX quiet_hours_guild_idx (guild_id)
+)
@@ -0,0 +1,5 @@
+DROP TABLE rotation_modes;
+
+ALTER TABLE joinsounds
+DROP COLUMN weight,
+DROP COLUMN played_in_cycle;
@@ -0,0 +1,12 @@
+ALTER TABLE joinsounds
+ADD COLUMN weight INT NOT NULL DEFAULT 1,
+ADD COLUMN played_in_cycle BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE rotation_modes (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    discord_id VARCHAR(255) NOT NULL,
+    guild_id VARCHAR(255),
+    kind VARCHAR(32) NOT NULL,
+    mode VARCHAR(32) NOT NULL,
+    INDEX rotation_modes_discord_idx (discord_id)
+);
@@ -0,0 +1,4 @@
+ALTER TABLE joinsounds
+DROP COLUMN library_sound_id;
+
+DROP TABLE library_sounds;
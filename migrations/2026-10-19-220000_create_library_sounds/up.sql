@@ -0,0 +1,10 @@
+CREATE TABLE library_sounds (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    discord_id VARCHAR(255) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    file_path VARCHAR(255) NOT NULL,
+    UNIQUE INDEX library_sounds_name_idx (discord_id, name)
+);
+
+ALTER TABLE joinsounds
+ADD COLUMN library_sound_id INT NULL;
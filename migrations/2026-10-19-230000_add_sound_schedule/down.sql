@@ -0,0 +1,5 @@
+DROP INDEX joinsounds_ends_at_idx ON joinsounds;
+
+ALTER TABLE joinsounds
+DROP COLUMN starts_at,
+DROP COLUMN ends_at;
@@ -0,0 +1,5 @@
+ALTER TABLE joinsounds
+ADD COLUMN starts_at TIMESTAMP NULL,
+ADD COLUMN ends_at TIMESTAMP NULL;
+
+CREATE INDEX joinsounds_ends_at_idx ON joinsounds (ends_at);
@@ -0,0 +1,4 @@
+CREATE TABLE user_settings (
+    discord_id VARCHAR(255) PRIMARY KEY,
+    timezone VARCHAR(64) NOT NULL DEFAULT 'UTC'
+)
@@ -0,0 +1,4 @@
+ALTER TABLE joinsounds
+DROP COLUMN time_start,
+DROP COLUMN time_end,
+DROP COLUMN weekdays;
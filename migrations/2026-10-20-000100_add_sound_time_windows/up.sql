@@ -0,0 +1,4 @@
+ALTER TABLE joinsounds
+ADD COLUMN time_start TIME NULL,
+ADD COLUMN time_end TIME NULL,
+ADD COLUMN weekdays INT NULL;
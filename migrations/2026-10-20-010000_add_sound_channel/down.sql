@@ -0,0 +1,7 @@
+ALTER TABLE rotation_modes
+DROP COLUMN channel_id;
+
+DROP INDEX channel_idx ON joinsounds;
+
+ALTER TABLE joinsounds
+DROP COLUMN channel_id;
@@ -0,0 +1,7 @@
+ALTER TABLE joinsounds
+ADD COLUMN channel_id VARCHAR(255) NULL;
+
+CREATE INDEX channel_idx ON joinsounds (channel_id);
+
+ALTER TABLE rotation_modes
+ADD COLUMN channel_id VARCHAR(255) NULL;
@@ -0,0 +1,4 @@
+ALTER TABLE guild_settings
+DROP COLUMN theme_policy;
+
+DROP TABLE channel_themes;
@@ -0,0 +1,10 @@
+CREATE TABLE channel_themes (
+    guild_id VARCHAR(255) NOT NULL,
+    channel_id VARCHAR(255) NOT NULL,
+    file_path VARCHAR(255) NOT NULL,
+    first_join_only BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (guild_id, channel_id)
+);
+
+ALTER TABLE guild_settings
+ADD COLUMN theme_policy VARCHAR(32) NOT NULL DEFAULT 'theme';
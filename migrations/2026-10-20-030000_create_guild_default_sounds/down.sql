@@ -0,0 +1,3 @@
+DROP TABLE voice_members;
+
+DROP TABLE guild_default_sounds;
@@ -0,0 +1,12 @@
+CREATE TABLE guild_default_sounds (
+    guild_id VARCHAR(255) NOT NULL PRIMARY KEY,
+    file_path VARCHAR(255) NOT NULL,
+    first_join_only BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE voice_members (
+    guild_id VARCHAR(255) NOT NULL,
+    discord_id VARCHAR(255) NOT NULL,
+    first_joined_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (guild_id, discord_id)
+);
@@ -0,0 +1,2 @@
+-- Seeded members can't be told apart from real joins, so they stay.
+SELECT 1;
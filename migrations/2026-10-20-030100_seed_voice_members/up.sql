@@ -0,0 +1,8 @@
+-- Members who already have a sound or a cooldown in a server have joined
+-- voice there before, so their next join isn't their first.
+INSERT IGNORE INTO voice_members (guild_id, discord_id)
+SELECT DISTINCT guild_id, discord_id FROM joinsounds
+WHERE guild_id IS NOT NULL AND discord_id IS NOT NULL;
+
+INSERT IGNORE INTO voice_members (guild_id, discord_id)
+SELECT DISTINCT guild_id, discord_id FROM member_cooldowns;
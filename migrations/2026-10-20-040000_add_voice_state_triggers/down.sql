@@ -0,0 +1,4 @@
+ALTER TABLE guild_settings
+DROP COLUMN play_on_stream,
+DROP COLUMN play_on_camera,
+DROP COLUMN play_on_unmute;
@@ -0,0 +1,4 @@
+ALTER TABLE guild_settings
+ADD COLUMN play_on_stream BOOLEAN NOT NULL DEFAULT TRUE,
+ADD COLUMN play_on_camera BOOLEAN NOT NULL DEFAULT TRUE,
+ADD COLUMN play_on_unmute BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,3 @@
+ALTER TABLE guild_settings
+DROP COLUMN group_window_seconds,
+DROP COLUMN group_sound_path;
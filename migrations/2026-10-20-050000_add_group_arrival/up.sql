@@ -0,0 +1,3 @@
+ALTER TABLE guild_settings
+ADD COLUMN group_window_seconds INTEGER NOT NULL DEFAULT 0,
+ADD COLUMN group_sound_path VARCHAR(255);
@@ -0,0 +1,4 @@
+ALTER TABLE guild_settings
+DROP COLUMN soundboard_role_id;
+
+DROP TABLE soundboard_sounds;
@@ -0,0 +1,11 @@
+CREATE TABLE soundboard_sounds (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    guild_id VARCHAR(255) NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    file_path VARCHAR(255) NOT NULL,
+    added_by VARCHAR(255) NULL,
+    UNIQUE INDEX soundboard_sounds_name_idx (guild_id, name)
+);
+
+ALTER TABLE guild_settings
+ADD COLUMN soundboard_role_id VARCHAR(255) NULL;
@@ -0,0 +1,2 @@
+ALTER TABLE guild_settings
+ADD COLUMN stop_role_id VARCHAR(255) NULL;
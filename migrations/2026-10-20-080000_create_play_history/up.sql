@@ -0,0 +1,12 @@
+CREATE TABLE play_history (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    guild_id VARCHAR(255) NOT NULL,
+    channel_id VARCHAR(255) NOT NULL,
+    discord_id VARCHAR(255) NOT NULL,
+    source VARCHAR(32) NOT NULL,
+    sound VARCHAR(255) NULL,
+    outcome VARCHAR(32) NOT NULL,
+    detail VARCHAR(255) NULL,
+    played_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    INDEX play_history_guild_idx (guild_id, played_at)
+);
@@ -0,0 +1 @@
+DROP INDEX play_history_discord_idx ON play_history
@@ -0,0 +1 @@
+CREATE INDEX play_history_discord_idx ON play_history (discord_id, played_at)
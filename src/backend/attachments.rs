@@ -1,13 +1,14 @@
 use chrono::Duration;
 use poise::serenity_prelude as serenity;
 use regex::Regex;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use tokio::fs;
 use tokio::{fs::OpenOptions, io::AsyncWriteExt};
-use tracing::info;
+use tracing::{info, warn};
 
 use crate::file;
+use crate::SoundKind;
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 
@@ -99,29 +100,25 @@ pub async fn get_length(attachment: serenity::Attachment) -> Result<Duration, Er
     Ok(Duration::seconds(duration_seconds.round() as i64))
 }
 
-pub async fn download_sound(
-    attachment: serenity::Attachment,
-    discord_id: serenity::UserId,
-    guild_id: Option<serenity::GuildId>,
-) -> Result<String, Error> {
-    // Build the destination folder
-    let folder = if let Some(guild) = guild_id {
-        Path::new("media")
-            .join(discord_id.to_string())
-            .join(guild.to_string())
-    } else {
-        Path::new("media").join(discord_id.to_string())
-    };
+/// A sound that has been downloaded and converted, but not stored yet.
+pub struct PendingSound {
+    pub attachment_id: serenity::AttachmentId,
+    pub temp_path: PathBuf,
+    pub filename: String,
+}
+
+pub async fn process_sound(attachment: serenity::Attachment) -> Result<PendingSound, Error> {
+    let attachment_id = attachment.id;
     let temp_file_path = Path::new("/tmp").join(format!(
         "joinsounds_{}_{}",
         attachment.id.get(),
         attachment.filename,
     ));
 
-    let mut file = folder.join(&attachment.filename);
+    let mut filename = attachment.filename.clone();
     if let Some(ref content_type) = attachment.content_type {
         if content_type.contains("video") {
-            let new_filename = if attachment.filename.contains('.') {
+            filename = if attachment.filename.contains('.') {
                 let re = Regex::new("(.*)\\.(.*)$").unwrap();
                 let (_, [name, _extension]) = re
                     .captures(&attachment.filename)
@@ -131,18 +128,82 @@ pub async fn download_sound(
             } else {
                 attachment.clone().filename + ".mp3"
             };
-            let new_filepath = folder.join(new_filename);
             save_video_as_audio(attachment, temp_file_path.as_path()).await?;
-            file = new_filepath;
         } else {
             save_attachment(attachment, temp_file_path.as_path()).await?;
         }
-        let temp_file = fs::File::open(temp_file_path).await?;
-        info!("saved as: {}", file.as_path().display());
-        file::save_file(file.clone(), temp_file).await?;
+        return Ok(PendingSound {
+            attachment_id,
+            temp_path: temp_file_path,
+            filename,
+        });
     }
+    Err(Box::new(std::io::Error::other("Could not save sound")))
+}
+
+pub async fn store_sound(
+    sound: &PendingSound,
+    discord_id: serenity::UserId,
+    guild_id: Option<serenity::GuildId>,
+    kind: SoundKind,
+) -> Result<String, Error> {
+    // Build the destination folder
+    let mut folder = if let Some(guild) = guild_id {
+        Path::new("media")
+            .join(discord_id.to_string())
+            .join(guild.to_string())
+    } else {
+        Path::new("media").join(discord_id.to_string())
+    };
+    // Join sounds keep the original layout, other kinds get their own folder
+    if kind != SoundKind::Join {
+        folder = folder.join(kind.as_str());
+    }
+
+    save_pending_sound(sound, &folder).await
+}
+
+/// Store a pending sound in the user's library folder.
+pub async fn store_library_sound(
+    sound: &PendingSound,
+    discord_id: serenity::UserId,
+) -> Result<String, Error> {
+    let folder = Path::new("media")
+        .join(discord_id.to_string())
+        .join("library");
+    save_pending_sound(sound, &folder).await
+}
+
+/// Store a pending sound that belongs to a guild rather than a member, such as
+/// a channel theme.
+pub async fn store_guild_sound(
+    sound: &PendingSound,
+    guild_id: serenity::GuildId,
+    category: &str,
+) -> Result<String, Error> {
+    let folder = Path::new("media")
+        .join("guilds")
+        .join(guild_id.to_string())
+        .join(category);
+    save_pending_sound(sound, &folder).await
+}
+
+async fn save_pending_sound(sound: &PendingSound, folder: &Path) -> Result<String, Error> {
+    // Each sound gets its own folder, so a set can hold files with the same name
+    let file = folder
+        .join(sound.attachment_id.to_string())
+        .join(&sound.filename);
+    let temp_file = fs::File::open(&sound.temp_path).await?;
+    info!("saved as: {}", file.as_path().display());
+    file::save_file(file.clone(), temp_file).await?;
     if let Some(path_str) = file.to_str() {
         return Ok(String::from(path_str));
     }
     Err(Box::new(std::io::Error::other("Could not save sound")))
 }
+
+pub async fn discard_sound(sound: &PendingSound) {
+    if let Err(why) = fs::remove_file(&sound.temp_path).await {
+        warn!("Could not remove pending sound: {}", why);
+    }
+}
@@ -0,0 +1,93 @@
+use chrono::Duration;
+use diesel::prelude::*;
+use poise::serenity_prelude as serenity;
+
+use super::database::connect;
+use super::models::GuildSettings;
+use super::schema::member_cooldowns;
+
+fn ensure_member_cooldown(
+    connection: &mut MysqlConnection,
+    guild: serenity::GuildId,
+    user: serenity::UserId,
+) {
+    diesel::insert_or_ignore_into(member_cooldowns::table)
+        .values((
+            member_cooldowns::guild_id.eq(guild.to_string()),
+            member_cooldowns::discord_id.eq(user.to_string()),
+        ))
+        .execute(connection)
+        .expect("Error creating member cooldown");
+}
+
+fn get_member_cooldown(
+    guild: serenity::GuildId,
+    user: serenity::UserId,
+) -> Option<(Option<i32>, Option<chrono::NaiveDateTime>)> {
+    let connection = &mut connect();
+    member_cooldowns::table
+        .filter(member_cooldowns::guild_id.eq(guild.to_string()))
+        .filter(member_cooldowns::discord_id.eq(user.to_string()))
+        .select((
+            member_cooldowns::cooldown_seconds,
+            member_cooldowns::last_triggered,
+        ))
+        .first(connection)
+        .optional()
+        .unwrap_or_default()
+}
+
+/// Set a cooldown for one member that overrides the guild cooldown.
+/// Passing `None` removes the override.
+pub fn set_member_cooldown(guild: serenity::GuildId, user: serenity::UserId, seconds: Option<i32>) {
+    let connection = &mut connect();
+    ensure_member_cooldown(connection, guild, user);
+    diesel::update(member_cooldowns::table)
+        .filter(member_cooldowns::guild_id.eq(guild.to_string()))
+        .filter(member_cooldowns::discord_id.eq(user.to_string()))
+        .set(member_cooldowns::cooldown_seconds.eq(seconds))
+        .execute(connection)
+        .expect("Error setting member cooldown");
+}
+
+/// The cooldown that applies to a member, their override if they have one.
+pub fn get_cooldown(
+    guild: serenity::GuildId,
+    user: serenity::UserId,
+    settings: &GuildSettings,
+) -> Duration {
+    let seconds = match get_member_cooldown(guild, user) {
+        Some((Some(seconds), _)) => seconds,
+        _ => settings.cooldown_seconds,
+    };
+    Duration::seconds(seconds.into())
+}
+
+/// Record that a member's sound was played, starting their cooldown.
+pub fn set_last_triggered(guild: serenity::GuildId, user: serenity::UserId) {
+    let connection = &mut connect();
+    ensure_member_cooldown(connection, guild, user);
+    diesel::update(member_cooldowns::table)
+        .filter(member_cooldowns::guild_id.eq(guild.to_string()))
+        .filter(member_cooldowns::discord_id.eq(user.to_string()))
+        .set(member_cooldowns::last_triggered.eq(chrono::Utc::now().naive_utc()))
+        .execute(connection)
+        .expect("Error setting last triggered");
+}
+
+/// How long until a member's sound can play again, if it is on cooldown.
+pub fn remaining_cooldown(
+    guild: serenity::GuildId,
+    user: serenity::UserId,
+    settings: &GuildSettings,
+) -> Option<Duration> {
+    let (member_cooldown, last_triggered) = get_member_cooldown(guild, user)?;
+    let cooldown = member_cooldown.unwrap_or(settings.cooldown_seconds);
+    let remaining =
+        last_triggered? + Duration::seconds(cooldown.into()) - chrono::Utc::now().naive_utc();
+    if remaining > Duration::zero() {
+        Some(remaining)
+    } else {
+        None
+    }
+}
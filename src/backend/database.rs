@@ -1,5 +1,6 @@
 use super::models::NewJoinSound;
 use super::schema;
+use super::{Schedule, SoundKind};
 use diesel::prelude::*;
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 use std::env;
@@ -20,7 +21,11 @@ pub fn migrate() {
 pub fn create_new_joinsound(
     user_id: poise::serenity_prelude::UserId,
     guild_id: Option<poise::serenity_prelude::GuildId>,
+    channel_id: Option<poise::serenity_prelude::ChannelId>,
+    kind: SoundKind,
     file_path: String,
+    library_sound_id: Option<i32>,
+    schedule: Schedule,
 ) {
     let connection = &mut connect();
     let guild_string: String;
@@ -31,10 +36,19 @@ pub fn create_new_joinsound(
         }
         None => None,
     };
+    let channel_string = channel_id.map(|channel| channel.to_string());
     let new_sound = NewJoinSound {
         discord_id: &user_id.to_string(),
         guild_id: guild_option,
         file_path: &file_path,
+        kind: kind.as_str(),
+        library_sound_id,
+        starts_at: schedule.starts_at,
+        ends_at: schedule.ends_at,
+        time_start: schedule.time_start,
+        time_end: schedule.time_end,
+        weekdays: schedule.weekdays,
+        channel_id: channel_string.as_deref(),
     };
     diesel::insert_into(schema::joinsounds::table)
         .values(&new_sound)
@@ -45,6 +59,7 @@ pub fn create_new_joinsound(
 pub fn update_joinsound(
     user_id: poise::serenity_prelude::UserId,
     guild_id: Option<poise::serenity_prelude::GuildId>,
+    kind: SoundKind,
     file_path: String,
 ) {
     if let Some(guild) = guild_id {
@@ -55,9 +70,18 @@ pub fn update_joinsound(
             discord_id: &user_id.to_string(),
             guild_id: guild_option,
             file_path: &file_path.to_string(),
+            kind: kind.as_str(),
+            library_sound_id: None,
+            starts_at: None,
+            ends_at: None,
+            time_start: None,
+            time_end: None,
+            weekdays: None,
+            channel_id: None,
         };
         diesel::update(schema::joinsounds::table)
             .filter(schema::joinsounds::discord_id.eq(user_id.to_string()))
+            .filter(schema::joinsounds::kind.eq(kind.as_str()))
             .filter(schema::joinsounds::guild_id.eq(&guild_str))
             .set(new_sound)
             .execute(connection)
@@ -68,9 +92,18 @@ pub fn update_joinsound(
             discord_id: &user_id.to_string(),
             guild_id: None,
             file_path: &file_path.to_string(),
+            kind: kind.as_str(),
+            library_sound_id: None,
+            starts_at: None,
+            ends_at: None,
+            time_start: None,
+            time_end: None,
+            weekdays: None,
+            channel_id: None,
         };
         diesel::update(schema::joinsounds::table)
             .filter(schema::joinsounds::discord_id.eq(user_id.to_string()))
+            .filter(schema::joinsounds::kind.eq(kind.as_str()))
             .filter(schema::joinsounds::guild_id.is_null())
             .set(new_sound)
             .execute(connection)
@@ -0,0 +1,91 @@
+use diesel::prelude::*;
+use poise::serenity_prelude as serenity;
+use std::path::PathBuf;
+use tracing::warn;
+
+use super::attachments::{self, PendingSound};
+use super::database::connect;
+use super::file;
+use super::models::GuildDefaultSound;
+use super::schema::{guild_default_sounds, voice_members};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+pub fn get_default_sound(guild: serenity::GuildId) -> Option<GuildDefaultSound> {
+    let connection = &mut connect();
+    guild_default_sounds::table
+        .find(guild.to_string())
+        .select(GuildDefaultSound::as_select())
+        .first(connection)
+        .ok()
+}
+
+pub async fn get_default_sound_file(sound: &GuildDefaultSound) -> Result<PathBuf, Error> {
+    Ok(file::canonicalize_file_path(PathBuf::from(&sound.file_path)).await?)
+}
+
+/// Use a pending sound as the guild's default sound, replacing the old one.
+pub async fn set_default_sound(
+    guild: serenity::GuildId,
+    sound: &PendingSound,
+    first_join_only: bool,
+) -> Result<(), Error> {
+    let old_sound = get_default_sound(guild);
+    let file_path = attachments::store_guild_sound(sound, guild, "default").await?;
+    let connection = &mut connect();
+    diesel::replace_into(guild_default_sounds::table)
+        .values((
+            guild_default_sounds::guild_id.eq(guild.to_string()),
+            guild_default_sounds::file_path.eq(file_path),
+            guild_default_sounds::first_join_only.eq(first_join_only),
+        ))
+        .execute(connection)
+        .expect("Error saving default sound");
+    attachments::discard_sound(sound).await;
+    if let Some(old_sound) = old_sound {
+        if let Err(why) = file::delete_file_if_exists(PathBuf::from(&old_sound.file_path)).await {
+            warn!("Error deleting old default sound: {why}");
+        }
+    }
+    Ok(())
+}
+
+pub async fn remove_default_sound(guild: serenity::GuildId) -> Result<(), Error> {
+    let Some(sound) = get_default_sound(guild) else {
+        return Err(Box::new(std::io::Error::other(
+            "This server has no default sound!",
+        )));
+    };
+    file::delete_file_if_exists(PathBuf::from(&sound.file_path)).await?;
+    let connection = &mut connect();
+    diesel::delete(guild_default_sounds::table.find(guild.to_string()))
+        .execute(connection)
+        .expect("Error removing default sound");
+    Ok(())
+}
+
+/// Remember that a member has joined a voice channel in a guild. Returns
+/// whether this was their first time. Call it on every join, even when no
+/// sound plays, so later joins aren't mistaken for the first. Members are
+/// only known from when this was first deployed, seeded with those who
+/// already had sounds or cooldowns in the guild.
+pub fn record_voice_join(guild: serenity::GuildId, user: serenity::UserId) -> bool {
+    let connection = &mut connect();
+    diesel::insert_or_ignore_into(voice_members::table)
+        .values((
+            voice_members::guild_id.eq(guild.to_string()),
+            voice_members::discord_id.eq(user.to_string()),
+        ))
+        .execute(connection)
+        .map(|inserted| inserted > 0)
+        .unwrap_or(false)
+}
+
+/// Forget which guilds a user has joined voice channels in.
+pub fn forget_voice_member(user: serenity::UserId) {
+    let connection = &mut connect();
+    diesel::delete(voice_members::table)
+        .filter(voice_members::discord_id.eq(user.to_string()))
+        .execute(connection)
+        .expect("Error removing voice members");
+}
@@ -129,18 +129,14 @@ pub async fn save_file(path: PathBuf, file: File) -> Result<(), Error> {
 
 pub async fn delete_file(path: PathBuf) -> Result<(), Error> {
     if is_s3_mode() {
-        if let Ok(bucket) = get_bucket().await {
-            if bucket
-                .delete_object(path.to_str().unwrap_or(""))
-                .await
-                .is_ok()
-            {
-                Ok(())
-            } else {
-                Err(Error::from(ErrorKind::NotFound))
-            }
-        } else {
-            Err(Error::from(ErrorKind::NotFound))
+        let bucket = get_bucket().await.map_err(Error::other)?;
+        // Only a missing object is NotFound, so callers can tell it apart
+        // from a bucket or credentials problem
+        match bucket.delete_object(path.to_str().unwrap_or("")).await {
+            Ok(response) if response.status_code() == 404 => Err(Error::from(ErrorKind::NotFound)),
+            Ok(_) => Ok(()),
+            Err(S3Error::HttpFailWithBody(404, _)) => Err(Error::from(ErrorKind::NotFound)),
+            Err(why) => Err(Error::other(why)),
         }
     } else {
         remove_file(path.clone()).await?;
@@ -157,3 +153,11 @@ pub async fn delete_file(path: PathBuf) -> Result<(), Error> {
         Ok(())
     }
 }
+
+/// Delete a file, treating one that is already gone as deleted.
+pub async fn delete_file_if_exists(path: PathBuf) -> Result<(), Error> {
+    match delete_file(path).await {
+        Err(why) if why.kind() == ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
@@ -0,0 +1,675 @@
+use chrono::NaiveTime;
+use chrono_tz::Tz;
+use diesel::prelude::*;
+use poise::serenity_prelude as serenity;
+use std::path::PathBuf;
+use tracing::warn;
+
+use super::attachments::{self, PendingSound};
+use super::database::connect;
+use super::file;
+use super::models::GuildSettings;
+use super::schema::{channel_filters, guild_settings, move_triggers, quiet_hours};
+use super::time::in_time_window;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+pub const DEFAULT_COOLDOWN_SECONDS: i32 = 30;
+
+/// Make sure the guild has a settings row, so updates only need to set the
+/// columns they change and everything else keeps its default.
+fn ensure_guild_settings(connection: &mut MysqlConnection, guild: serenity::GuildId) {
+    diesel::insert_or_ignore_into(guild_settings::table)
+        .values(guild_settings::guild_id.eq(guild.to_string()))
+        .execute(connection)
+        .expect("Error creating guild settings");
+}
+
+/// Get a guild's settings, or the defaults if it has never changed any.
+pub fn get_guild_settings(guild: serenity::GuildId) -> GuildSettings {
+    let connection = &mut connect();
+    let settings = guild_settings::table
+        .find(guild.to_string())
+        .select(GuildSettings::as_select())
+        .first(connection)
+        .optional();
+    match settings {
+        Ok(settings) => settings.unwrap_or_else(|| GuildSettings::defaults(guild)),
+        Err(why) => {
+            warn!("Error loading guild settings: {why}");
+            GuildSettings::defaults(guild)
+        }
+    }
+}
+
+pub fn set_cooldown(guild: serenity::GuildId, seconds: i32) {
+    let connection = &mut connect();
+    ensure_guild_settings(connection, guild);
+    diesel::update(guild_settings::table)
+        .filter(guild_settings::guild_id.eq(guild.to_string()))
+        .set(guild_settings::cooldown_seconds.eq(seconds))
+        .execute(connection)
+        .expect("Error setting cooldown");
+}
+
+pub fn set_idle_timeout(guild: serenity::GuildId, minutes: i32, stay_while_occupied: bool) {
+    let connection = &mut connect();
+    ensure_guild_settings(connection, guild);
+    diesel::update(guild_settings::table)
+        .filter(guild_settings::guild_id.eq(guild.to_string()))
+        .set((
+            guild_settings::idle_timeout_minutes.eq(minutes),
+            guild_settings::stay_while_occupied.eq(stay_while_occupied),
+        ))
+        .execute(connection)
+        .expect("Error setting idle timeout");
+}
+
+/// When a member moving between voice channels should hear their sound.
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum MovePolicy {
+    #[name = "Never"]
+    Never,
+    #[name = "Always"]
+    Always,
+    #[name = "Only between listed channels"]
+    Listed,
+}
+
+impl MovePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MovePolicy::Never => "never",
+            MovePolicy::Always => "always",
+            MovePolicy::Listed => "listed",
+        }
+    }
+
+    pub fn parse(value: &str) -> MovePolicy {
+        match value {
+            "always" => MovePolicy::Always,
+            "listed" => MovePolicy::Listed,
+            _ => MovePolicy::Never,
+        }
+    }
+}
+
+/// What to do with a sound for another channel while the bot is still playing
+/// sounds in its current one.
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum BusyPolicy {
+    #[name = "Move to the new channel right away"]
+    Move,
+    #[name = "Wait until the current channel is done"]
+    Queue,
+    #[name = "Skip the sound"]
+    Skip,
+}
+
+impl BusyPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BusyPolicy::Move => "move",
+            BusyPolicy::Queue => "queue",
+            BusyPolicy::Skip => "skip",
+        }
+    }
+
+    pub fn parse(value: &str) -> BusyPolicy {
+        match value {
+            "move" => BusyPolicy::Move,
+            "skip" => BusyPolicy::Skip,
+            _ => BusyPolicy::Queue,
+        }
+    }
+}
+
+/// What to do with a new sound when the guild's queue is already full.
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum DropPolicy {
+    #[name = "Drop the new sound"]
+    Newest,
+    #[name = "Drop the oldest queued sound"]
+    Oldest,
+}
+
+impl DropPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DropPolicy::Newest => "newest",
+            DropPolicy::Oldest => "oldest",
+        }
+    }
+
+    pub fn parse(value: &str) -> DropPolicy {
+        match value {
+            "oldest" => DropPolicy::Oldest,
+            _ => DropPolicy::Newest,
+        }
+    }
+}
+
+/// Which sound plays when a member joins a channel with a theme and also has
+/// a sound of their own.
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum ThemePolicy {
+    #[name = "Play the channel theme"]
+    Theme,
+    #[name = "Play the member's sound"]
+    User,
+    #[name = "Play the channel theme, then the member's sound"]
+    Both,
+}
+
+impl ThemePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThemePolicy::Theme => "theme",
+            ThemePolicy::User => "user",
+            ThemePolicy::Both => "both",
+        }
+    }
+
+    pub fn parse(value: &str) -> ThemePolicy {
+        match value {
+            "user" => ThemePolicy::User,
+            "both" => ThemePolicy::Both,
+            _ => ThemePolicy::Theme,
+        }
+    }
+}
+
+impl GuildSettings {
+    /// The settings of a guild without a settings row, matching the column
+    /// defaults.
+    pub fn defaults(guild: serenity::GuildId) -> GuildSettings {
+        GuildSettings {
+            guild_id: guild.to_string(),
+            max_queue_length: 5,
+            queue_drop_policy: DropPolicy::Newest.as_str().to_string(),
+            cooldown_seconds: DEFAULT_COOLDOWN_SECONDS,
+            idle_timeout_minutes: 0,
+            stay_while_occupied: false,
+            play_on_join: true,
+            move_policy: MovePolicy::Never.as_str().to_string(),
+            busy_policy: BusyPolicy::Queue.as_str().to_string(),
+            ignore_afk_channel: false,
+            ignore_deafened: false,
+            ignore_muted: false,
+            min_other_members: 0,
+            timezone: "UTC".to_string(),
+            paused_until: None,
+            theme_policy: ThemePolicy::Theme.as_str().to_string(),
+            play_on_stream: true,
+            play_on_camera: true,
+            play_on_unmute: true,
+            group_window_seconds: 0,
+            group_sound_path: None,
+            soundboard_role_id: None,
+            stop_role_id: None,
+        }
+    }
+
+    pub fn move_policy(&self) -> MovePolicy {
+        MovePolicy::parse(&self.move_policy)
+    }
+
+    pub fn busy_policy(&self) -> BusyPolicy {
+        BusyPolicy::parse(&self.busy_policy)
+    }
+
+    pub fn theme_policy(&self) -> ThemePolicy {
+        ThemePolicy::parse(&self.theme_policy)
+    }
+
+    pub fn queue_drop_policy(&self) -> DropPolicy {
+        DropPolicy::parse(&self.queue_drop_policy)
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone.parse().unwrap_or(Tz::UTC)
+    }
+
+    /// When the current pause ends, if sounds are paused right now.
+    pub fn paused_until(&self) -> Option<chrono::NaiveDateTime> {
+        self.paused_until
+            .filter(|paused_until| *paused_until > chrono::Utc::now().naive_utc())
+    }
+}
+
+pub fn set_voice_events(guild: serenity::GuildId, play_on_join: bool, move_policy: MovePolicy) {
+    let connection = &mut connect();
+    ensure_guild_settings(connection, guild);
+    diesel::update(guild_settings::table)
+        .filter(guild_settings::guild_id.eq(guild.to_string()))
+        .set((
+            guild_settings::play_on_join.eq(play_on_join),
+            guild_settings::move_policy.eq(move_policy.as_str()),
+        ))
+        .execute(connection)
+        .expect("Error setting voice events");
+}
+
+/// Choose which changes to a member's voice state play their sound.
+pub fn set_voice_state_triggers(
+    guild: serenity::GuildId,
+    play_on_stream: bool,
+    play_on_camera: bool,
+    play_on_unmute: bool,
+) {
+    let connection = &mut connect();
+    ensure_guild_settings(connection, guild);
+    diesel::update(guild_settings::table)
+        .filter(guild_settings::guild_id.eq(guild.to_string()))
+        .set((
+            guild_settings::play_on_stream.eq(play_on_stream),
+            guild_settings::play_on_camera.eq(play_on_camera),
+            guild_settings::play_on_unmute.eq(play_on_unmute),
+        ))
+        .execute(connection)
+        .expect("Error setting voice state triggers");
+}
+
+/// Collapse joins into the same channel within `window_seconds` into one
+/// sound, or turn it off with a window of zero. A new `sound` replaces the
+/// group sound, and `clear_sound` removes it so groups hear the first
+/// member's sound. Otherwise the current group sound is kept.
+pub async fn set_group_arrival(
+    guild: serenity::GuildId,
+    window_seconds: i32,
+    sound: Option<&PendingSound>,
+    clear_sound: bool,
+) -> Result<(), Error> {
+    let old_path = get_guild_settings(guild).group_sound_path;
+    let group_sound_path = match sound {
+        Some(sound) => Some(attachments::store_guild_sound(sound, guild, "group").await?),
+        None if clear_sound => None,
+        None => old_path.clone(),
+    };
+    let connection = &mut connect();
+    ensure_guild_settings(connection, guild);
+    diesel::update(guild_settings::table)
+        .filter(guild_settings::guild_id.eq(guild.to_string()))
+        .set((
+            guild_settings::group_window_seconds.eq(window_seconds),
+            guild_settings::group_sound_path.eq(&group_sound_path),
+        ))
+        .execute(connection)
+        .expect("Error setting group arrival");
+    if let Some(sound) = sound {
+        attachments::discard_sound(sound).await;
+    }
+    if let Some(old_path) = old_path.filter(|old_path| Some(old_path) != group_sound_path.as_ref())
+    {
+        if let Err(why) = file::delete_file_if_exists(PathBuf::from(old_path)).await {
+            warn!("Error deleting old group sound: {why}");
+        }
+    }
+    Ok(())
+}
+
+/// Let members with `role` manage the soundboard, as well as admins.
+pub fn set_soundboard_role(guild: serenity::GuildId, role: Option<serenity::RoleId>) {
+    let connection = &mut connect();
+    ensure_guild_settings(connection, guild);
+    diesel::update(guild_settings::table)
+        .filter(guild_settings::guild_id.eq(guild.to_string()))
+        .set(guild_settings::soundboard_role_id.eq(role.map(|role| role.to_string())))
+        .execute(connection)
+        .expect("Error setting soundboard role");
+}
+
+/// Let members with `role` stop anyone's sound, as well as admins.
+pub fn set_stop_role(guild: serenity::GuildId, role: Option<serenity::RoleId>) {
+    let connection = &mut connect();
+    ensure_guild_settings(connection, guild);
+    diesel::update(guild_settings::table)
+        .filter(guild_settings::guild_id.eq(guild.to_string()))
+        .set(guild_settings::stop_role_id.eq(role.map(|role| role.to_string())))
+        .execute(connection)
+        .expect("Error setting stop role");
+}
+
+/// Get the `(from, to)` channel pairs that play a sound when the move policy
+/// is [`MovePolicy::Listed`]. `None` matches any channel.
+pub fn get_move_triggers(
+    guild: serenity::GuildId,
+) -> Vec<(Option<serenity::ChannelId>, Option<serenity::ChannelId>)> {
+    let connection = &mut connect();
+    move_triggers::table
+        .filter(move_triggers::guild_id.eq(guild.to_string()))
+        .select((move_triggers::from_channel_id, move_triggers::to_channel_id))
+        .load::<(Option<String>, Option<String>)>(connection)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(from, to)| (parse_channel(from), parse_channel(to)))
+        .collect()
+}
+
+fn parse_channel(channel: Option<String>) -> Option<serenity::ChannelId> {
+    channel.and_then(|channel| channel.parse::<u64>().ok().map(serenity::ChannelId::new))
+}
+
+pub fn add_move_trigger(
+    guild: serenity::GuildId,
+    from: Option<serenity::ChannelId>,
+    to: Option<serenity::ChannelId>,
+) {
+    let connection = &mut connect();
+    diesel::insert_into(move_triggers::table)
+        .values((
+            move_triggers::guild_id.eq(guild.to_string()),
+            move_triggers::from_channel_id.eq(from.map(|channel| channel.to_string())),
+            move_triggers::to_channel_id.eq(to.map(|channel| channel.to_string())),
+        ))
+        .execute(connection)
+        .expect("Error adding move trigger");
+}
+
+pub fn remove_move_trigger(
+    guild: serenity::GuildId,
+    from: Option<serenity::ChannelId>,
+    to: Option<serenity::ChannelId>,
+) -> usize {
+    let connection = &mut connect();
+    let mut query = diesel::delete(move_triggers::table)
+        .filter(move_triggers::guild_id.eq(guild.to_string()))
+        .into_boxed();
+    query = match from {
+        Some(from) => query.filter(move_triggers::from_channel_id.eq(from.to_string())),
+        None => query.filter(move_triggers::from_channel_id.is_null()),
+    };
+    query = match to {
+        Some(to) => query.filter(move_triggers::to_channel_id.eq(to.to_string())),
+        None => query.filter(move_triggers::to_channel_id.is_null()),
+    };
+    query
+        .execute(connection)
+        .expect("Error removing move trigger")
+}
+
+/// Whether a member moving from `from` to `to` should hear their sound.
+pub fn should_play_on_move(
+    guild: serenity::GuildId,
+    settings: &GuildSettings,
+    from: serenity::ChannelId,
+    to: serenity::ChannelId,
+) -> bool {
+    match settings.move_policy() {
+        MovePolicy::Never => false,
+        MovePolicy::Always => true,
+        MovePolicy::Listed => {
+            get_move_triggers(guild)
+                .into_iter()
+                .any(|(from_trigger, to_trigger)| {
+                    from_trigger.is_none_or(|channel| channel == from)
+                        && to_trigger.is_none_or(|channel| channel == to)
+                })
+        }
+    }
+}
+
+pub fn set_busy_policy(guild: serenity::GuildId, busy_policy: BusyPolicy) {
+    let connection = &mut connect();
+    ensure_guild_settings(connection, guild);
+    diesel::update(guild_settings::table)
+        .filter(guild_settings::guild_id.eq(guild.to_string()))
+        .set(guild_settings::busy_policy.eq(busy_policy.as_str()))
+        .execute(connection)
+        .expect("Error setting busy policy");
+}
+
+/// Limit how many sounds can be queued in the guild, and choose which sound
+/// is dropped when the queue is full.
+pub fn set_queue(guild: serenity::GuildId, max_length: i32, drop_policy: DropPolicy) {
+    let connection = &mut connect();
+    ensure_guild_settings(connection, guild);
+    diesel::update(guild_settings::table)
+        .filter(guild_settings::guild_id.eq(guild.to_string()))
+        .set((
+            guild_settings::max_queue_length.eq(max_length),
+            guild_settings::queue_drop_policy.eq(drop_policy.as_str()),
+        ))
+        .execute(connection)
+        .expect("Error setting queue");
+}
+
+pub fn set_theme_policy(guild: serenity::GuildId, theme_policy: ThemePolicy) {
+    let connection = &mut connect();
+    ensure_guild_settings(connection, guild);
+    diesel::update(guild_settings::table)
+        .filter(guild_settings::guild_id.eq(guild.to_string()))
+        .set(guild_settings::theme_policy.eq(theme_policy.as_str()))
+        .execute(connection)
+        .expect("Error setting theme policy");
+}
+
+pub fn set_skip_rules(
+    guild: serenity::GuildId,
+    ignore_afk_channel: bool,
+    ignore_deafened: bool,
+    ignore_muted: bool,
+    min_other_members: i32,
+) {
+    let connection = &mut connect();
+    ensure_guild_settings(connection, guild);
+    diesel::update(guild_settings::table)
+        .filter(guild_settings::guild_id.eq(guild.to_string()))
+        .set((
+            guild_settings::ignore_afk_channel.eq(ignore_afk_channel),
+            guild_settings::ignore_deafened.eq(ignore_deafened),
+            guild_settings::ignore_muted.eq(ignore_muted),
+            guild_settings::min_other_members.eq(min_other_members),
+        ))
+        .execute(connection)
+        .expect("Error setting skip rules");
+}
+
+/// Which list a channel filter belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum ChannelList {
+    #[name = "Allow"]
+    Allow,
+    #[name = "Deny"]
+    Deny,
+}
+
+impl ChannelList {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelList::Allow => "allow",
+            ChannelList::Deny => "deny",
+        }
+    }
+
+    pub fn parse(value: &str) -> ChannelList {
+        match value {
+            "allow" => ChannelList::Allow,
+            _ => ChannelList::Deny,
+        }
+    }
+}
+
+/// Get the voice channels and categories on the allow and deny lists.
+pub fn get_channel_filters(guild: serenity::GuildId) -> Vec<(serenity::ChannelId, ChannelList)> {
+    let connection = &mut connect();
+    channel_filters::table
+        .filter(channel_filters::guild_id.eq(guild.to_string()))
+        .select((channel_filters::channel_id, channel_filters::list))
+        .load::<(String, String)>(connection)
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(channel, list)| {
+            parse_channel(Some(channel)).map(|channel| (channel, ChannelList::parse(&list)))
+        })
+        .collect()
+}
+
+/// Put a channel or category on a list, replacing any list it was on before.
+pub fn add_channel_filter(
+    guild: serenity::GuildId,
+    channel: serenity::ChannelId,
+    list: ChannelList,
+) {
+    remove_channel_filter(guild, channel);
+    let connection = &mut connect();
+    diesel::insert_into(channel_filters::table)
+        .values((
+            channel_filters::guild_id.eq(guild.to_string()),
+            channel_filters::channel_id.eq(channel.to_string()),
+            channel_filters::list.eq(list.as_str()),
+        ))
+        .execute(connection)
+        .expect("Error adding channel filter");
+}
+
+pub fn remove_channel_filter(guild: serenity::GuildId, channel: serenity::ChannelId) -> usize {
+    let connection = &mut connect();
+    diesel::delete(channel_filters::table)
+        .filter(channel_filters::guild_id.eq(guild.to_string()))
+        .filter(channel_filters::channel_id.eq(channel.to_string()))
+        .execute(connection)
+        .expect("Error removing channel filter")
+}
+
+/// Whether sounds may play in a voice channel. Denied channels never play.
+/// If the allow list is not empty, only channels on it play. A category on
+/// a list applies to every channel in it.
+pub fn is_channel_allowed(
+    guild: serenity::GuildId,
+    channel: serenity::ChannelId,
+    category: Option<serenity::ChannelId>,
+) -> bool {
+    channel_allowed(&get_channel_filters(guild), channel, category)
+}
+
+fn channel_allowed(
+    filters: &[(serenity::ChannelId, ChannelList)],
+    channel: serenity::ChannelId,
+    category: Option<serenity::ChannelId>,
+) -> bool {
+    let matches = |filter: &serenity::ChannelId| *filter == channel || Some(*filter) == category;
+    if filters
+        .iter()
+        .any(|(filter, list)| *list == ChannelList::Deny && matches(filter))
+    {
+        return false;
+    }
+    let mut allowed = filters
+        .iter()
+        .filter(|(_, list)| *list == ChannelList::Allow)
+        .peekable();
+    allowed.peek().is_none() || allowed.any(|(filter, _)| matches(filter))
+}
+
+pub fn set_timezone(guild: serenity::GuildId, timezone: Tz) {
+    let connection = &mut connect();
+    ensure_guild_settings(connection, guild);
+    diesel::update(guild_settings::table)
+        .filter(guild_settings::guild_id.eq(guild.to_string()))
+        .set(guild_settings::timezone.eq(timezone.name()))
+        .execute(connection)
+        .expect("Error setting timezone");
+}
+
+/// Pause sounds in the guild until `until`, or resume them with `None`.
+pub fn set_paused_until(guild: serenity::GuildId, until: Option<chrono::NaiveDateTime>) {
+    let connection = &mut connect();
+    ensure_guild_settings(connection, guild);
+    diesel::update(guild_settings::table)
+        .filter(guild_settings::guild_id.eq(guild.to_string()))
+        .set(guild_settings::paused_until.eq(until))
+        .execute(connection)
+        .expect("Error setting pause");
+}
+
+/// Get the daily `(start, end)` windows when sounds are suppressed, in the
+/// guild's timezone.
+pub fn get_quiet_hours(guild: serenity::GuildId) -> Vec<(NaiveTime, NaiveTime)> {
+    let connection = &mut connect();
+    quiet_hours::table
+        .filter(quiet_hours::guild_id.eq(guild.to_string()))
+        .select((quiet_hours::start_time, quiet_hours::end_time))
+        .order(quiet_hours::start_time)
+        .load(connection)
+        .unwrap_or_default()
+}
+
+pub fn add_quiet_hours(guild: serenity::GuildId, start: NaiveTime, end: NaiveTime) {
+    let connection = &mut connect();
+    diesel::insert_into(quiet_hours::table)
+        .values((
+            quiet_hours::guild_id.eq(guild.to_string()),
+            quiet_hours::start_time.eq(start),
+            quiet_hours::end_time.eq(end),
+        ))
+        .execute(connection)
+        .expect("Error adding quiet hours");
+}
+
+pub fn remove_quiet_hours(guild: serenity::GuildId, start: NaiveTime, end: NaiveTime) -> usize {
+    let connection = &mut connect();
+    diesel::delete(quiet_hours::table)
+        .filter(quiet_hours::guild_id.eq(guild.to_string()))
+        .filter(quiet_hours::start_time.eq(start))
+        .filter(quiet_hours::end_time.eq(end))
+        .execute(connection)
+        .expect("Error removing quiet hours")
+}
+
+/// Whether the guild is in one of its quiet hours windows right now.
+pub fn is_quiet_hours(guild: serenity::GuildId, settings: &GuildSettings) -> bool {
+    let now = chrono::Utc::now()
+        .with_timezone(&settings.timezone())
+        .time();
+    get_quiet_hours(guild)
+        .into_iter()
+        .any(|(start, end)| in_time_window(now, start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANNEL: serenity::ChannelId = serenity::ChannelId::new(1);
+    const OTHER_CHANNEL: serenity::ChannelId = serenity::ChannelId::new(2);
+    const CATEGORY: serenity::ChannelId = serenity::ChannelId::new(3);
+
+    #[test]
+    fn every_channel_is_allowed_without_filters() {
+        assert!(channel_allowed(&[], CHANNEL, None));
+        assert!(channel_allowed(&[], CHANNEL, Some(CATEGORY)));
+    }
+
+    #[test]
+    fn deny_wins_over_allow() {
+        let filters = [(CHANNEL, ChannelList::Allow), (CATEGORY, ChannelList::Deny)];
+        assert!(!channel_allowed(&filters, CHANNEL, Some(CATEGORY)));
+        let filters = [(CATEGORY, ChannelList::Allow), (CHANNEL, ChannelList::Deny)];
+        assert!(!channel_allowed(&filters, CHANNEL, Some(CATEGORY)));
+    }
+
+    #[test]
+    fn allow_list_restricts_to_its_channels() {
+        let filters = [(CHANNEL, ChannelList::Allow)];
+        assert!(channel_allowed(&filters, CHANNEL, None));
+        assert!(!channel_allowed(&filters, OTHER_CHANNEL, None));
+    }
+
+    #[test]
+    fn deny_list_only_blocks_its_channels() {
+        let filters = [(CHANNEL, ChannelList::Deny)];
+        assert!(!channel_allowed(&filters, CHANNEL, None));
+        assert!(channel_allowed(&filters, OTHER_CHANNEL, None));
+    }
+
+    #[test]
+    fn category_applies_to_its_channels() {
+        let filters = [(CATEGORY, ChannelList::Allow)];
+        assert!(channel_allowed(&filters, CHANNEL, Some(CATEGORY)));
+        assert!(!channel_allowed(&filters, OTHER_CHANNEL, None));
+        let filters = [(CATEGORY, ChannelList::Deny)];
+        assert!(!channel_allowed(&filters, CHANNEL, Some(CATEGORY)));
+        assert!(channel_allowed(&filters, OTHER_CHANNEL, None));
+    }
+}
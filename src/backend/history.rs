@@ -0,0 +1,131 @@
+use diesel::prelude::*;
+use poise::serenity_prelude as serenity;
+use std::path::Path;
+use tracing::warn;
+
+use super::database::connect;
+use super::models::PlayEvent;
+use super::schema::play_history;
+use super::SoundKind;
+
+/// What a played sound was for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaySource {
+    /// A member's own sound for a voice event.
+    Member(SoundKind),
+    Theme,
+    Default,
+    Group,
+    /// A member's sound played with `/play`.
+    OnDemand,
+    Soundboard,
+}
+
+impl PlaySource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlaySource::Member(kind) => kind.as_str(),
+            PlaySource::Theme => "theme",
+            PlaySource::Default => "default",
+            PlaySource::Group => "group",
+            PlaySource::OnDemand => "play",
+            PlaySource::Soundboard => "soundboard",
+        }
+    }
+}
+
+/// How an attempt to play a sound ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlayOutcome {
+    Played,
+    /// The guild's rules or a cooldown kept the sound from playing.
+    Skipped(String),
+    Failed(String),
+}
+
+impl PlayOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlayOutcome::Played => "played",
+            PlayOutcome::Skipped(_) => "skipped",
+            PlayOutcome::Failed(_) => "failed",
+        }
+    }
+
+    fn detail(&self) -> Option<&str> {
+        match self {
+            PlayOutcome::Played => None,
+            PlayOutcome::Skipped(detail) | PlayOutcome::Failed(detail) => Some(detail),
+        }
+    }
+}
+
+/// The name a sound file is shown with in the history.
+pub fn sound_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Add an attempt to play `sound` for `user` to the guild's history.
+pub fn record_play(
+    guild: serenity::GuildId,
+    channel: serenity::ChannelId,
+    user: serenity::UserId,
+    source: PlaySource,
+    sound: Option<&str>,
+    outcome: PlayOutcome,
+) {
+    let connection = &mut connect();
+    let detail = outcome
+        .detail()
+        .map(|detail| detail.chars().take(255).collect::<String>());
+    let result = diesel::insert_into(play_history::table)
+        .values((
+            play_history::guild_id.eq(guild.to_string()),
+            play_history::channel_id.eq(channel.to_string()),
+            play_history::discord_id.eq(user.to_string()),
+            play_history::source.eq(source.as_str()),
+            play_history::sound.eq(sound),
+            play_history::outcome.eq(outcome.as_str()),
+            play_history::detail.eq(detail),
+        ))
+        .execute(connection);
+    // Losing a history row shouldn't stop the sound from playing
+    if let Err(why) = result {
+        warn!("Error saving play history: {}", why);
+    }
+}
+
+/// Get the most recent attempts to play a sound in a guild, newest first.
+pub fn get_play_history(guild: serenity::GuildId, limit: i64) -> Vec<PlayEvent> {
+    let connection = &mut connect();
+    play_history::table
+        .filter(play_history::guild_id.eq(guild.to_string()))
+        .select(PlayEvent::as_select())
+        .order((play_history::played_at.desc(), play_history::id.desc()))
+        .limit(limit)
+        .load(connection)
+        .unwrap_or_default()
+}
+
+/// Get the last sound that actually played in a guild.
+pub fn get_last_play(guild: serenity::GuildId) -> Option<PlayEvent> {
+    let connection = &mut connect();
+    play_history::table
+        .filter(play_history::guild_id.eq(guild.to_string()))
+        .filter(play_history::outcome.eq(PlayOutcome::Played.as_str()))
+        .select(PlayEvent::as_select())
+        .order((play_history::played_at.desc(), play_history::id.desc()))
+        .first(connection)
+        .ok()
+}
+
+/// Forget every sound played for a user.
+pub fn remove_play_history(user: serenity::UserId) {
+    let connection = &mut connect();
+    diesel::delete(play_history::table)
+        .filter(play_history::discord_id.eq(user.to_string()))
+        .execute(connection)
+        .expect("Error removing play history");
+}
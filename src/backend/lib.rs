@@ -1,7 +1,7 @@
 #[macro_use]
 extern crate diesel;
 
-use attachments::validate_attachment;
+use attachments::{validate_attachment, PendingSound};
 use chrono::Duration;
 use diesel::dsl::{exists, select};
 use diesel::prelude::*;
@@ -11,24 +11,100 @@ use tracing::{error, info};
 use poise::serenity_prelude as serenity;
 
 pub mod attachments;
+pub mod cooldowns;
 pub mod database;
+pub mod default_sound;
 pub mod file;
+pub mod guild_settings;
+pub mod history;
+pub mod library;
 pub mod models;
+pub mod rotation;
 pub mod schema;
+pub mod soundboard;
+pub mod stats;
+pub mod themes;
+pub mod time;
+pub mod user_settings;
 
 use database::connect;
+use models::SoundEntry;
+
+/// How many sounds a user can keep for each scope and kind.
+pub const MAX_SOUNDS_PER_SCOPE: usize = 10;
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
 
-pub fn has_sound(in_discord_id: serenity::UserId, in_guild_id: Option<serenity::GuildId>) -> bool {
-    use self::schema::joinsounds::dsl::{discord_id, guild_id, joinsounds};
+/// The voice event a sound is played for.
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum SoundKind {
+    #[name = "join"]
+    Join,
+    #[name = "leave"]
+    Leave,
+    #[name = "stream"]
+    Stream,
+    #[name = "camera"]
+    Camera,
+    #[name = "unmute"]
+    Unmute,
+}
+
+impl SoundKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SoundKind::Join => "join",
+            SoundKind::Leave => "leave",
+            SoundKind::Stream => "stream",
+            SoundKind::Camera => "camera",
+            SoundKind::Unmute => "unmute",
+        }
+    }
+
+    pub fn parse(value: &str) -> SoundKind {
+        match value {
+            "leave" => SoundKind::Leave,
+            "stream" => SoundKind::Stream,
+            "camera" => SoundKind::Camera,
+            "unmute" => SoundKind::Unmute,
+            _ => SoundKind::Join,
+        }
+    }
+}
+
+/// When a sound plays. Sounds without a start or an end always play, and
+/// sounds without a time of day or weekdays play at any time.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Schedule {
+    pub starts_at: Option<chrono::NaiveDateTime>,
+    pub ends_at: Option<chrono::NaiveDateTime>,
+    pub time_start: Option<chrono::NaiveTime>,
+    pub time_end: Option<chrono::NaiveTime>,
+    /// Days of the week as a bitmask, with Monday as the lowest bit.
+    pub weekdays: Option<i32>,
+}
+
+/// Check whether a user has a sound of `kind` in a scope. Local sounds for
+/// other channels than `in_channel_id` don't count.
+pub fn has_sound(
+    in_discord_id: serenity::UserId,
+    in_guild_id: Option<serenity::GuildId>,
+    in_channel_id: Option<serenity::ChannelId>,
+    kind: SoundKind,
+) -> bool {
+    use self::schema::joinsounds::dsl::{
+        channel_id, discord_id, guild_id, joinsounds, kind as sound_kind,
+    };
     let connection = &mut connect();
     // Check local sound
     if let Some(guild) = in_guild_id {
+        let in_channel = in_channel_id.map(|channel| channel.to_string());
         let res = select(exists(
             joinsounds
                 .filter(discord_id.eq(in_discord_id.to_string()))
-                .filter(guild_id.eq(guild.to_string())),
+                .filter(sound_kind.eq(kind.as_str()))
+                .filter(guild_id.eq(guild.to_string()))
+                .filter(channel_id.is_null().or(channel_id.eq(in_channel))),
         ))
         .get_result::<bool>(connection);
         res.unwrap_or(false)
@@ -37,6 +113,7 @@ pub fn has_sound(in_discord_id: serenity::UserId, in_guild_id: Option<serenity::
         let res = select(exists(
             joinsounds
                 .filter(discord_id.eq(in_discord_id.to_string()))
+                .filter(sound_kind.eq(kind.as_str()))
                 .filter(guild_id.is_null()),
         ))
         .get_result::<bool>(connection);
@@ -51,138 +128,169 @@ pub fn has_any_sound(in_discord_id: serenity::UserId) -> bool {
         joinsounds.filter(discord_id.eq(in_discord_id.to_string())),
     ))
     .get_result::<bool>(connection);
-    res.unwrap_or(false)
+    res.unwrap_or(false) || !library::list_library_sounds(in_discord_id).is_empty()
 }
 
+/// Get every sound in a user's set for a scope, in the order they were added.
+/// Channel sets are part of a guild, so `channel` needs a `guild` to match.
+pub fn list_sounds(
+    user_id: serenity::UserId,
+    guild: Option<serenity::GuildId>,
+    channel: Option<serenity::ChannelId>,
+    kind: SoundKind,
+) -> Vec<SoundEntry> {
+    let connection = &mut connect();
+    let mut query = schema::joinsounds::table
+        .filter(schema::joinsounds::discord_id.eq(user_id.to_string()))
+        .filter(schema::joinsounds::kind.eq(kind.as_str()))
+        .select(SoundEntry::as_select())
+        .order(schema::joinsounds::id)
+        .into_boxed();
+    query = match guild {
+        Some(guild_id) => query.filter(schema::joinsounds::guild_id.eq(guild_id.to_string())),
+        None => query.filter(schema::joinsounds::guild_id.is_null()),
+    };
+    query = match channel {
+        Some(channel_id) => query.filter(schema::joinsounds::channel_id.eq(channel_id.to_string())),
+        None => query.filter(schema::joinsounds::channel_id.is_null()),
+    };
+    query.load(connection).unwrap_or_default()
+}
+
+async fn sound_file(sound: &SoundEntry) -> Result<PathBuf, String> {
+    match &sound.file_path {
+        Some(joinsound_path) => file::canonicalize_file_path(joinsound_path.into())
+            .await
+            .map_err(|why| format!("Could not get join sound file: {why}")),
+        None => Err("File path is null".to_string()),
+    }
+}
+
+/// Pick the sound to play for a user joining `channel` and count it as played
+/// for their rotation.
 pub async fn get_sound(
     user_id: serenity::UserId,
     guild: serenity::GuildId,
+    channel: serenity::ChannelId,
+    kind: SoundKind,
 ) -> Result<PathBuf, String> {
-    let connection = &mut connect();
-
-    // Check local sound first
-    if let Ok(path) = schema::joinsounds::table
-        .filter(schema::joinsounds::discord_id.eq(user_id.to_string()))
-        .filter(schema::joinsounds::guild_id.eq(guild.to_string()))
-        .select(schema::joinsounds::file_path)
-        .first::<Option<String>>(connection)
-    {
-        if let Some(joinsound_path) = path {
-            if let Err(why) = set_last_played(user_id, Some(guild)) {
-                error!("Error setting last played: {}", why);
-            }
-            let joinsound_file_path = file::canonicalize_file_path(joinsound_path.clone().into())
-                .await
-                .expect("Could not get join sound file");
-            Ok(joinsound_file_path)
-        } else {
-            Err("File path is null".to_string())
-        }
-    } else {
-        // Check global sound
-        if let Ok(path) = schema::joinsounds::table
-            .filter(schema::joinsounds::discord_id.eq(user_id.to_string()))
-            .filter(schema::joinsounds::guild_id.is_null())
-            .select(schema::joinsounds::file_path)
-            .first::<Option<String>>(connection)
-        {
-            if let Some(joinsound_path) = path {
-                if let Err(why) = set_last_played(user_id, None) {
-                    error!("Error setting last played: {}", why);
-                }
-                let joinsound_file_path =
-                    file::canonicalize_file_path(joinsound_path.clone().into())
-                        .await
-                        .expect("Could not get join sound file");
-                Ok(joinsound_file_path)
-            } else {
-                Err("File path is null".to_string())
-            }
-        } else {
-            Err("No joinsound entry".to_string())
-        }
+    let (sound, shuffled) = select_sound(user_id, guild, channel, kind)?;
+    if let Some(sounds) = shuffled {
+        rotation::mark_played(&sounds, &sound);
     }
+    if let Err(why) = set_last_played(sound.id) {
+        error!("Error setting last played: {}", why);
+    }
+    sound_file(&sound).await
 }
 
-pub async fn get_sound_path(
+/// Get the file of the sound a user would play joining `channel`, without
+/// counting it as played, so their rotation is left as it was.
+pub async fn peek_sound(
     user_id: serenity::UserId,
-    guild: Option<serenity::GuildId>,
+    guild: serenity::GuildId,
+    channel: serenity::ChannelId,
+    kind: SoundKind,
 ) -> Result<PathBuf, String> {
-    let connection = &mut connect();
+    let (sound, _) = select_sound(user_id, guild, channel, kind)?;
+    sound_file(&sound).await
+}
 
-    // Check local sound first
-    if let Some(guild_id) = guild {
-        if let Ok(path) = schema::joinsounds::table
-            .filter(schema::joinsounds::discord_id.eq(user_id.to_string()))
-            .filter(schema::joinsounds::guild_id.eq(guild_id.to_string()))
-            .select(schema::joinsounds::file_path)
-            .first::<Option<String>>(connection)
-        {
-            if let Some(joinsound_path) = path {
-                let joinsound_file_path =
-                    file::canonicalize_file_path(joinsound_path.clone().into())
-                        .await
-                        .expect("Could not get join sound file");
-                Ok(joinsound_file_path)
-            } else {
-                Err("File path is null".to_string())
-            }
-        } else {
-            Err("No local joinsound entry".to_string())
-        }
-    } else {
-        // Check global sound
-        if let Ok(path) = schema::joinsounds::table
-            .filter(schema::joinsounds::discord_id.eq(user_id.to_string()))
-            .filter(schema::joinsounds::guild_id.is_null())
-            .select(schema::joinsounds::file_path)
-            .first::<Option<String>>(connection)
-        {
-            if let Some(joinsound_path) = path {
-                let joinsound_file_path =
-                    file::canonicalize_file_path(joinsound_path.clone().into())
-                        .await
-                        .expect("Could not get join sound file");
-                Ok(joinsound_file_path)
-            } else {
-                Err("File path is null".to_string())
-            }
-        } else {
-            Err("No global joinsound entry".to_string())
-        }
+/// Pick the sound for a user joining `channel`. Scheduled sounds that are
+/// active right now come first, then sounds for the current time of day or
+/// weekday in the user's timezone. Otherwise the first of the user's channel,
+/// local and global sets that has sounds is used. When that set is shuffled,
+/// it is returned too so the pick can be marked as played.
+fn select_sound(
+    user_id: serenity::UserId,
+    guild: serenity::GuildId,
+    channel: serenity::ChannelId,
+    kind: SoundKind,
+) -> Result<(SoundEntry, Option<Vec<SoundEntry>>), String> {
+    let now = chrono::Utc::now().naive_utc();
+    let local_time = chrono::Utc::now()
+        .with_timezone(&user_settings::get_timezone(user_id))
+        .naive_local();
+    let scopes = [
+        (Some(guild), Some(channel)),
+        (Some(guild), None),
+        (None, None),
+    ];
+    let sets = scopes
+        .iter()
+        .map(|(guild, channel)| list_sounds(user_id, *guild, *channel, kind))
+        .collect::<Vec<_>>();
+    let variant = {
+        let tiers: [&dyn Fn(&SoundEntry) -> bool; 2] = [
+            &|sound| sound.is_scheduled() && sound.is_active(now) && sound.matches_time(local_time),
+            &|sound| {
+                !sound.is_scheduled() && sound.has_time_window() && sound.matches_time(local_time)
+            },
+        ];
+        tiers.iter().find_map(|in_tier| {
+            sets.iter().find_map(|sounds| {
+                let variants = sounds
+                    .iter()
+                    .filter(|sound| in_tier(sound))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                rotation::pick_sound(rotation::RotationMode::Random, &variants).cloned()
+            })
+        })
+    };
+    if let Some(sound) = variant {
+        return Ok((sound, None));
     }
+
+    let is_plain = |sound: &SoundEntry| !sound.is_scheduled() && !sound.has_time_window();
+    let Some(((scope_guild, scope_channel), sounds)) = scopes
+        .iter()
+        .copied()
+        .zip(sets)
+        .map(|(scope, sounds)| {
+            (
+                scope,
+                sounds.into_iter().filter(is_plain).collect::<Vec<_>>(),
+            )
+        })
+        .find(|(_, sounds)| !sounds.is_empty())
+    else {
+        return Err("No joinsound entry".to_string());
+    };
+    let mode = rotation::get_rotation_mode(user_id, scope_guild, scope_channel, kind);
+    let Some(sound) = rotation::pick_sound(mode, &sounds).cloned() else {
+        return Err("No joinsound entry".to_string());
+    };
+    let shuffled = (mode == rotation::RotationMode::Shuffle).then_some(sounds);
+    Ok((sound, shuffled))
 }
 
-pub fn get_last_played(
+/// Get the files for every sound in a user's set for a scope.
+pub async fn get_sound_paths(
     user_id: serenity::UserId,
     guild: Option<serenity::GuildId>,
-) -> Option<chrono::NaiveDateTime> {
-    let connection = &mut connect();
-
-    if let Some(guild_id) = guild {
-        // Check local sound first
-        schema::joinsounds::table
-            .filter(schema::joinsounds::discord_id.eq(user_id.to_string()))
-            .filter(schema::joinsounds::guild_id.eq(guild_id.to_string()))
-            .select(schema::joinsounds::last_played)
-            .first::<Option<chrono::NaiveDateTime>>(connection)
-            .unwrap_or_default()
-    } else {
-        // Check global sound
-        schema::joinsounds::table
-            .filter(schema::joinsounds::discord_id.eq(user_id.to_string()))
-            .filter(schema::joinsounds::guild_id.is_null())
-            .select(schema::joinsounds::last_played)
-            .first::<Option<chrono::NaiveDateTime>>(connection)
-            .unwrap_or_default()
+    channel: Option<serenity::ChannelId>,
+    kind: SoundKind,
+) -> Result<Vec<(SoundEntry, PathBuf)>, String> {
+    let sounds = list_sounds(user_id, guild, channel, kind);
+    if sounds.is_empty() {
+        return Err(match (guild, channel) {
+            (_, Some(_)) => "No channel joinsound entry".to_string(),
+            (Some(_), None) => "No local joinsound entry".to_string(),
+            (None, None) => "No global joinsound entry".to_string(),
+        });
+    }
+    let mut paths = vec![];
+    for sound in sounds {
+        let path = sound_file(&sound).await?;
+        paths.push((sound, path));
     }
+    Ok(paths)
 }
 
-pub async fn upload_sound(
-    user_id: serenity::UserId,
-    attachment: serenity::Attachment,
-    guild_id: Option<serenity::GuildId>,
-) -> Result<(), Error> {
+/// Validate and process an attachment without touching the user's current
+/// joinsound. The result is kept pending until it is confirmed or discarded.
+pub async fn prepare_sound(attachment: serenity::Attachment) -> Result<PendingSound, Error> {
     // check if attachment is a video
     if !validate_attachment(attachment.clone()) {
         return Err(Box::new(std::io::Error::other(
@@ -195,139 +303,155 @@ pub async fn upload_sound(
             if length > Duration::seconds(15) {
                 Err(Box::new(std::io::Error::other("Video is too long")))
             } else {
-                let file_path = attachments::download_sound(attachment, user_id, guild_id).await?;
-                database::create_new_joinsound(user_id, guild_id, file_path);
-                Ok(())
+                attachments::process_sound(attachment).await
             }
         }
         Err(e) => Err(Box::new(std::io::Error::other(e))),
     }
 }
 
-pub async fn update_sound(
+/// Add a pending sound to the user's set for a scope. The pending file is
+/// left for the caller to discard.
+pub async fn confirm_sound(
     user_id: serenity::UserId,
-    attachment: serenity::Attachment,
+    sound: &PendingSound,
     guild_id: Option<serenity::GuildId>,
+    channel_id: Option<serenity::ChannelId>,
+    kind: SoundKind,
+    schedule: Schedule,
 ) -> Result<(), Error> {
-    // check if attachment is a video
-    if !validate_attachment(attachment.clone()) {
-        return Err(Box::new(std::io::Error::other(
-            "Attachment is not a video or an audio file.",
-        )));
-    }
-    // check video length
-    match attachments::get_length(attachment.clone()).await {
-        Ok(length) => {
-            if length > Duration::seconds(15) {
-                Err(Box::new(std::io::Error::other("Video is too long")))
-            } else {
-                // remove the sound first
-                if has_sound(user_id, guild_id) {
-                    remove_sound(user_id, guild_id).await?;
-                }
-                let file_path = attachments::download_sound(attachment, user_id, guild_id).await?;
-                // Database entry is deleted at this point, create the new sound
-                database::create_new_joinsound(user_id, guild_id, file_path);
-                Ok(())
-            }
-        }
-        Err(e) => Err(Box::new(std::io::Error::other(e))),
+    if list_sounds(user_id, guild_id, channel_id, kind).len() >= MAX_SOUNDS_PER_SCOPE {
+        return Err(Box::new(std::io::Error::other(format!(
+            "You already have {MAX_SOUNDS_PER_SCOPE} sounds here, remove one first."
+        ))));
     }
+    let file_path = attachments::store_sound(sound, user_id, guild_id, kind).await?;
+    database::create_new_joinsound(
+        user_id, guild_id, channel_id, kind, file_path, None, schedule,
+    );
+    Ok(())
 }
 
-pub fn set_last_played(
-    user_id: serenity::UserId,
-    guild: Option<serenity::GuildId>,
-) -> Result<(), Error> {
+pub fn set_last_played(sound_id: i32) -> Result<(), Error> {
     let connection = &mut connect();
     let timestamp = chrono::Utc::now().naive_utc();
-    if let Some(guild_id) = guild {
-        diesel::update(schema::joinsounds::table)
-            .filter(schema::joinsounds::discord_id.eq(user_id.to_string()))
-            .filter(schema::joinsounds::guild_id.eq(guild_id.to_string()))
-            .set(schema::joinsounds::last_played.eq(timestamp))
-            .execute(connection)
-            .expect("Error setting last played");
-    } else {
-        diesel::update(schema::joinsounds::table)
-            .filter(schema::joinsounds::discord_id.eq(user_id.to_string()))
-            .filter(schema::joinsounds::guild_id.is_null())
-            .set(schema::joinsounds::last_played.eq(timestamp))
-            .execute(connection)
-            .expect("Error setting last played");
-    }
+    diesel::update(schema::joinsounds::table.find(sound_id))
+        .set(schema::joinsounds::last_played.eq(timestamp))
+        .execute(connection)
+        .expect("Error setting last played");
     info!("Set last played to {}", timestamp);
     Ok(())
 }
 
+async fn delete_sound(sound: &SoundEntry) -> Result<(), Error> {
+    // Library files are shared between scopes and removed with the library sound
+    if let (Some(joinsound_path), None) = (&sound.file_path, sound.library_sound_id) {
+        file::delete_file_if_exists(PathBuf::from(joinsound_path)).await?;
+    }
+    let connection = &mut connect();
+    diesel::delete(schema::joinsounds::table.find(sound.id))
+        .execute(connection)
+        .expect("Error deleting joinsound");
+    Ok(())
+}
+
+/// Remove every sound in the user's set for a scope.
 pub async fn remove_sound(
     discord_id: serenity::UserId,
     guild_id: Option<serenity::GuildId>,
+    channel_id: Option<serenity::ChannelId>,
+    kind: SoundKind,
 ) -> Result<(), Error> {
-    if has_sound(discord_id, guild_id) {
-        if let Some(guild) = guild_id {
-            let connection = &mut connect();
-            let guild_str = guild.to_string();
-
-            // get file path to remove it
-            if let Ok(Some(joinsound_path)) = schema::joinsounds::table
-                .filter(schema::joinsounds::discord_id.eq(discord_id.to_string()))
-                .filter(schema::joinsounds::guild_id.eq(&guild_str))
-                .select(schema::joinsounds::file_path)
-                .first::<Option<String>>(connection)
-            {
-                file::delete_file(PathBuf::from(joinsound_path)).await?;
-            } else {
-                return Err(Box::new(std::io::Error::other("No sound to remove!")));
-            }
-
-            diesel::delete(schema::joinsounds::table)
-                .filter(schema::joinsounds::discord_id.eq(discord_id.to_string()))
-                .filter(schema::joinsounds::guild_id.eq(&guild_str))
-                .execute(connection)
-                .expect("Error deleting joinsound");
+    let sounds = list_sounds(discord_id, guild_id, channel_id, kind);
+    if sounds.is_empty() {
+        return Err(Box::new(std::io::Error::other("No sound to remove!")));
+    }
+    for sound in sounds {
+        delete_sound(&sound).await?;
+    }
+    Ok(())
+}
 
-            Ok(())
-        } else {
-            let connection = &mut connect();
-            // get file path to remove it
-            if let Ok(Some(joinsound_path)) = schema::joinsounds::table
-                .filter(schema::joinsounds::discord_id.eq(discord_id.to_string()))
-                .filter(schema::joinsounds::guild_id.is_null())
-                .select(schema::joinsounds::file_path)
-                .first::<Option<String>>(connection)
-            {
-                file::delete_file(PathBuf::from(joinsound_path)).await?;
-            } else {
-                return Err(Box::new(std::io::Error::other("No sound to remove!")));
-            }
+/// Remove one sound from the user's set for a scope, by its 1-based position
+/// in the set.
+pub async fn remove_sound_entry(
+    discord_id: serenity::UserId,
+    guild_id: Option<serenity::GuildId>,
+    channel_id: Option<serenity::ChannelId>,
+    kind: SoundKind,
+    number: usize,
+) -> Result<(), Error> {
+    let sounds = list_sounds(discord_id, guild_id, channel_id, kind);
+    match number.checked_sub(1).and_then(|index| sounds.get(index)) {
+        Some(sound) => delete_sound(sound).await,
+        None => Err(Box::new(std::io::Error::other(format!(
+            "There is no sound #{number} to remove!"
+        )))),
+    }
+}
 
-            diesel::delete(schema::joinsounds::table)
-                .filter(schema::joinsounds::discord_id.eq(discord_id.to_string()))
-                .filter(schema::joinsounds::guild_id.is_null())
-                .execute(connection)
-                .expect("Error deleting joinsound");
+/// Set how likely one sound in the user's set is to play in weighted random
+/// mode, by its 1-based position in the set.
+pub fn set_sound_weight(
+    discord_id: serenity::UserId,
+    guild_id: Option<serenity::GuildId>,
+    channel_id: Option<serenity::ChannelId>,
+    kind: SoundKind,
+    number: usize,
+    weight: i32,
+) -> Result<(), Error> {
+    let sounds = list_sounds(discord_id, guild_id, channel_id, kind);
+    let Some(sound) = number.checked_sub(1).and_then(|index| sounds.get(index)) else {
+        return Err(Box::new(std::io::Error::other(format!(
+            "There is no sound #{number}!"
+        ))));
+    };
+    let connection = &mut connect();
+    diesel::update(schema::joinsounds::table.find(sound.id))
+        .set(schema::joinsounds::weight.eq(weight))
+        .execute(connection)
+        .expect("Error setting sound weight");
+    Ok(())
+}
 
-            Ok(())
+/// Remove every scheduled sound that has ended.
+pub async fn remove_expired_sounds() -> Result<(), Error> {
+    let connection = &mut connect();
+    let expired = schema::joinsounds::table
+        .filter(schema::joinsounds::ends_at.le(chrono::Utc::now().naive_utc()))
+        .select(SoundEntry::as_select())
+        .load(connection)?;
+    if !expired.is_empty() {
+        info!("Removing {} expired sounds", expired.len());
+    }
+    for sound in expired {
+        // Keep going, so one broken sound doesn't hold up the rest
+        if let Err(why) = delete_sound(&sound).await {
+            error!("Error removing expired sound {}: {}", sound.id, why);
         }
-    } else {
-        Err(Box::new(std::io::Error::other("No sound to remove!")))
     }
+    Ok(())
 }
 
 pub async fn remove_all_sounds(discord_id: serenity::UserId) -> Result<(), Error> {
     let connection = &mut connect();
 
-    if let Ok(guilds) = schema::joinsounds::table
+    if let Ok(sounds) = schema::joinsounds::table
         .filter(schema::joinsounds::discord_id.eq(discord_id.to_string()))
-        .select(schema::joinsounds::guild_id)
-        .load::<Option<String>>(connection)
+        .select(SoundEntry::as_select())
+        .load(connection)
     {
-        for guild_id_str in guilds {
-            let guild_id =
-                guild_id_str.map(|guild| serenity::GuildId::from(guild.parse().unwrap_or(0)));
-            remove_sound(discord_id, guild_id).await?;
+        for sound in sounds {
+            delete_sound(&sound).await?;
         }
     }
+    rotation::remove_rotation_modes(discord_id);
+    user_settings::remove_user_settings(discord_id);
+    default_sound::forget_voice_member(discord_id);
+    soundboard::forget_uploader(discord_id);
+    history::remove_play_history(discord_id);
+    for sound in library::list_library_sounds(discord_id) {
+        library::remove_library_sound(discord_id, &sound.name).await?;
+    }
     Ok(())
 }
@@ -0,0 +1,132 @@
+use diesel::prelude::*;
+use poise::serenity_prelude as serenity;
+use std::path::PathBuf;
+
+use super::attachments::{self, PendingSound};
+use super::database::{self, connect};
+use super::models::LibrarySound;
+use super::schema::{joinsounds, library_sounds};
+use super::{file, list_sounds, Schedule, SoundKind, MAX_SOUNDS_PER_SCOPE};
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// How many sounds a user can keep in their library.
+pub const MAX_LIBRARY_SOUNDS: usize = 25;
+
+pub fn list_library_sounds(user_id: serenity::UserId) -> Vec<LibrarySound> {
+    let connection = &mut connect();
+    library_sounds::table
+        .filter(library_sounds::discord_id.eq(user_id.to_string()))
+        .select(LibrarySound::as_select())
+        .order(library_sounds::name)
+        .load(connection)
+        .unwrap_or_default()
+}
+
+pub fn get_library_sound(user_id: serenity::UserId, name: &str) -> Option<LibrarySound> {
+    let connection = &mut connect();
+    library_sounds::table
+        .filter(library_sounds::discord_id.eq(user_id.to_string()))
+        .filter(library_sounds::name.eq(name))
+        .select(LibrarySound::as_select())
+        .first(connection)
+        .ok()
+}
+
+/// Store a pending sound in the user's library under `name`.
+pub async fn add_library_sound(
+    user_id: serenity::UserId,
+    sound: &PendingSound,
+    name: &str,
+) -> Result<(), Error> {
+    if get_library_sound(user_id, name).is_some() {
+        return Err(Box::new(std::io::Error::other(format!(
+            "You already have a sound called {name}."
+        ))));
+    }
+    if list_library_sounds(user_id).len() >= MAX_LIBRARY_SOUNDS {
+        return Err(Box::new(std::io::Error::other(format!(
+            "Your library already has {MAX_LIBRARY_SOUNDS} sounds, remove one first."
+        ))));
+    }
+    let file_path = attachments::store_library_sound(sound, user_id).await?;
+    let connection = &mut connect();
+    diesel::insert_into(library_sounds::table)
+        .values((
+            library_sounds::discord_id.eq(user_id.to_string()),
+            library_sounds::name.eq(name),
+            library_sounds::file_path.eq(&file_path),
+        ))
+        .execute(connection)
+        .expect("Error saving library sound");
+    attachments::discard_sound(sound).await;
+    Ok(())
+}
+
+/// Add a library sound to the user's set for a scope, without copying the file.
+pub fn assign_library_sound(
+    user_id: serenity::UserId,
+    name: &str,
+    guild_id: Option<serenity::GuildId>,
+    channel_id: Option<serenity::ChannelId>,
+    kind: SoundKind,
+) -> Result<(), Error> {
+    let Some(sound) = get_library_sound(user_id, name) else {
+        return Err(Box::new(std::io::Error::other(format!(
+            "You don't have a sound called {name}."
+        ))));
+    };
+    let sounds = list_sounds(user_id, guild_id, channel_id, kind);
+    if sounds
+        .iter()
+        .any(|entry| entry.library_sound_id == Some(sound.id))
+    {
+        return Err(Box::new(std::io::Error::other(format!(
+            "{name} is already one of your sounds here."
+        ))));
+    }
+    if sounds.len() >= MAX_SOUNDS_PER_SCOPE {
+        return Err(Box::new(std::io::Error::other(format!(
+            "You already have {MAX_SOUNDS_PER_SCOPE} sounds here, remove one first."
+        ))));
+    }
+    database::create_new_joinsound(
+        user_id,
+        guild_id,
+        channel_id,
+        kind,
+        sound.file_path,
+        Some(sound.id),
+        Schedule::default(),
+    );
+    Ok(())
+}
+
+/// Count the scopes a library sound is used in.
+pub fn count_assignments(sound: &LibrarySound) -> i64 {
+    let connection = &mut connect();
+    joinsounds::table
+        .filter(joinsounds::library_sound_id.eq(sound.id))
+        .count()
+        .get_result(connection)
+        .unwrap_or(0)
+}
+
+/// Remove a library sound, along with every scope it is used in.
+pub async fn remove_library_sound(user_id: serenity::UserId, name: &str) -> Result<(), Error> {
+    let Some(sound) = get_library_sound(user_id, name) else {
+        return Err(Box::new(std::io::Error::other(format!(
+            "You don't have a sound called {name}."
+        ))));
+    };
+    file::delete_file(PathBuf::from(&sound.file_path)).await?;
+    let connection = &mut connect();
+    diesel::delete(joinsounds::table)
+        .filter(joinsounds::library_sound_id.eq(sound.id))
+        .execute(connection)
+        .expect("Error removing library sound assignments");
+    diesel::delete(library_sounds::table.find(sound.id))
+        .execute(connection)
+        .expect("Error removing library sound");
+    Ok(())
+}
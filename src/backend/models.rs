@@ -1,6 +1,11 @@
-use diesel::{Insertable, Queryable};
+use chrono::Datelike;
+use diesel::{Insertable, Queryable, Selectable};
 
-use super::schema::joinsounds;
+use super::schema::{
+    channel_themes, guild_default_sounds, guild_settings, joinsounds, library_sounds, play_history,
+    soundboard_sounds,
+};
+use super::time::in_time_window;
 
 #[derive(Queryable)]
 pub struct JoinSounds {
@@ -16,4 +21,144 @@ pub struct NewJoinSound<'a> {
     pub discord_id: &'a str,
     pub guild_id: Option<&'a str>,
     pub file_path: &'a str,
+    pub kind: &'a str,
+    pub library_sound_id: Option<i32>,
+    pub starts_at: Option<chrono::NaiveDateTime>,
+    pub ends_at: Option<chrono::NaiveDateTime>,
+    pub time_start: Option<chrono::NaiveTime>,
+    pub time_end: Option<chrono::NaiveTime>,
+    pub weekdays: Option<i32>,
+    pub channel_id: Option<&'a str>,
+}
+
+/// One of the sounds in a user's set for a scope.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = joinsounds)]
+pub struct SoundEntry {
+    pub id: i32,
+    pub file_path: Option<String>,
+    pub last_played: Option<chrono::NaiveDateTime>,
+    pub weight: i32,
+    pub played_in_cycle: bool,
+    pub library_sound_id: Option<i32>,
+    pub starts_at: Option<chrono::NaiveDateTime>,
+    pub ends_at: Option<chrono::NaiveDateTime>,
+    pub time_start: Option<chrono::NaiveTime>,
+    pub time_end: Option<chrono::NaiveTime>,
+    pub weekdays: Option<i32>,
+}
+
+impl SoundEntry {
+    /// Whether the sound only plays for a limited time.
+    pub fn is_scheduled(&self) -> bool {
+        self.starts_at.is_some() || self.ends_at.is_some()
+    }
+
+    /// Whether a scheduled sound should play at `now`.
+    pub fn is_active(&self, now: chrono::NaiveDateTime) -> bool {
+        self.starts_at.is_none_or(|starts_at| starts_at <= now)
+            && self.ends_at.is_none_or(|ends_at| now < ends_at)
+    }
+
+    /// Whether the sound only plays at certain times of day or on certain
+    /// days of the week.
+    pub fn has_time_window(&self) -> bool {
+        self.time_start.is_some() || self.time_end.is_some() || self.weekdays.is_some()
+    }
+
+    /// Whether the sound should play at `local_time`, in the user's timezone.
+    pub fn matches_time(&self, local_time: chrono::NaiveDateTime) -> bool {
+        let weekday_bit = 1 << local_time.weekday().num_days_from_monday();
+        let time = local_time.time();
+        let in_window = match (self.time_start, self.time_end) {
+            (None, None) => true,
+            (Some(start), None) => start <= time,
+            (None, Some(end)) => time < end,
+            (Some(start), Some(end)) => in_time_window(time, start, end),
+        };
+        in_window
+            && self
+                .weekdays
+                .is_none_or(|weekdays| weekdays & weekday_bit != 0)
+    }
+}
+
+/// A named sound in a user's library, which can be used in any scope.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = library_sounds)]
+pub struct LibrarySound {
+    pub id: i32,
+    pub name: String,
+    pub file_path: String,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = guild_settings)]
+pub struct GuildSettings {
+    pub guild_id: String,
+    pub max_queue_length: i32,
+    pub queue_drop_policy: String,
+    pub cooldown_seconds: i32,
+    pub idle_timeout_minutes: i32,
+    pub stay_while_occupied: bool,
+    pub play_on_join: bool,
+    pub move_policy: String,
+    pub busy_policy: String,
+    pub ignore_afk_channel: bool,
+    pub ignore_deafened: bool,
+    pub ignore_muted: bool,
+    pub min_other_members: i32,
+    pub timezone: String,
+    pub paused_until: Option<chrono::NaiveDateTime>,
+    pub theme_policy: String,
+    pub play_on_stream: bool,
+    pub play_on_camera: bool,
+    pub play_on_unmute: bool,
+    pub group_window_seconds: i32,
+    pub group_sound_path: Option<String>,
+    pub soundboard_role_id: Option<String>,
+    pub stop_role_id: Option<String>,
+}
+
+/// A sound an admin attached to a voice channel.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = channel_themes)]
+pub struct ChannelTheme {
+    pub guild_id: String,
+    pub channel_id: String,
+    pub file_path: String,
+    pub first_join_only: bool,
+}
+
+/// The sound a guild plays for members without a sound of their own.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = guild_default_sounds)]
+pub struct GuildDefaultSound {
+    pub guild_id: String,
+    pub file_path: String,
+    pub first_join_only: bool,
+}
+
+/// A named sound in a guild's shared soundboard.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = soundboard_sounds)]
+pub struct SoundboardSound {
+    pub id: i32,
+    pub name: String,
+    pub file_path: String,
+    pub added_by: Option<String>,
+}
+
+/// One attempt to play a sound, kept in the guild's play history.
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = play_history)]
+pub struct PlayEvent {
+    pub id: i32,
+    pub channel_id: String,
+    pub discord_id: String,
+    pub source: String,
+    pub sound: Option<String>,
+    pub outcome: String,
+    pub detail: Option<String>,
+    pub played_at: chrono::NaiveDateTime,
 }
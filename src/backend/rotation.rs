@@ -0,0 +1,252 @@
+use diesel::prelude::*;
+use poise::serenity_prelude as serenity;
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+
+use super::database::connect;
+use super::models::SoundEntry;
+use super::schema::{joinsounds, rotation_modes};
+use super::SoundKind;
+
+/// How the next sound is picked when a user has several sounds in a scope.
+#[derive(Debug, Clone, Copy, PartialEq, poise::ChoiceParameter)]
+pub enum RotationMode {
+    #[name = "random"]
+    Random,
+    #[name = "weighted random"]
+    Weighted,
+    #[name = "round-robin"]
+    RoundRobin,
+    #[name = "shuffle without repeats"]
+    Shuffle,
+}
+
+impl RotationMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RotationMode::Random => "random",
+            RotationMode::Weighted => "weighted",
+            RotationMode::RoundRobin => "round_robin",
+            RotationMode::Shuffle => "shuffle",
+        }
+    }
+
+    pub fn parse(value: &str) -> RotationMode {
+        match value {
+            "weighted" => RotationMode::Weighted,
+            "round_robin" => RotationMode::RoundRobin,
+            "shuffle" => RotationMode::Shuffle,
+            _ => RotationMode::Random,
+        }
+    }
+}
+
+pub fn get_rotation_mode(
+    user_id: serenity::UserId,
+    guild_id: Option<serenity::GuildId>,
+    channel_id: Option<serenity::ChannelId>,
+    kind: SoundKind,
+) -> RotationMode {
+    let connection = &mut connect();
+    let mut query = rotation_modes::table
+        .filter(rotation_modes::discord_id.eq(user_id.to_string()))
+        .filter(rotation_modes::kind.eq(kind.as_str()))
+        .select(rotation_modes::mode)
+        .into_boxed();
+    query = match guild_id {
+        Some(guild) => query.filter(rotation_modes::guild_id.eq(guild.to_string())),
+        None => query.filter(rotation_modes::guild_id.is_null()),
+    };
+    query = match channel_id {
+        Some(channel) => query.filter(rotation_modes::channel_id.eq(channel.to_string())),
+        None => query.filter(rotation_modes::channel_id.is_null()),
+    };
+    query
+        .first::<String>(connection)
+        .map(|mode| RotationMode::parse(&mode))
+        .unwrap_or(RotationMode::Random)
+}
+
+pub fn set_rotation_mode(
+    user_id: serenity::UserId,
+    guild_id: Option<serenity::GuildId>,
+    channel_id: Option<serenity::ChannelId>,
+    kind: SoundKind,
+    mode: RotationMode,
+) {
+    let connection = &mut connect();
+    let mut delete = diesel::delete(rotation_modes::table)
+        .filter(rotation_modes::discord_id.eq(user_id.to_string()))
+        .filter(rotation_modes::kind.eq(kind.as_str()))
+        .into_boxed();
+    delete = match guild_id {
+        Some(guild) => delete.filter(rotation_modes::guild_id.eq(guild.to_string())),
+        None => delete.filter(rotation_modes::guild_id.is_null()),
+    };
+    delete = match channel_id {
+        Some(channel) => delete.filter(rotation_modes::channel_id.eq(channel.to_string())),
+        None => delete.filter(rotation_modes::channel_id.is_null()),
+    };
+    delete
+        .execute(connection)
+        .expect("Error clearing rotation mode");
+    diesel::insert_into(rotation_modes::table)
+        .values((
+            rotation_modes::discord_id.eq(user_id.to_string()),
+            rotation_modes::guild_id.eq(guild_id.map(|guild| guild.to_string())),
+            rotation_modes::channel_id.eq(channel_id.map(|channel| channel.to_string())),
+            rotation_modes::kind.eq(kind.as_str()),
+            rotation_modes::mode.eq(mode.as_str()),
+        ))
+        .execute(connection)
+        .expect("Error setting rotation mode");
+}
+
+/// Forget the rotation modes for every scope of a user.
+pub fn remove_rotation_modes(user_id: serenity::UserId) {
+    let connection = &mut connect();
+    diesel::delete(rotation_modes::table)
+        .filter(rotation_modes::discord_id.eq(user_id.to_string()))
+        .execute(connection)
+        .expect("Error removing rotation modes");
+}
+
+/// Pick the next sound to play from a set according to the rotation mode.
+pub fn pick_sound(mode: RotationMode, sounds: &[SoundEntry]) -> Option<&SoundEntry> {
+    let mut rng = thread_rng();
+    match mode {
+        RotationMode::Random => sounds.choose(&mut rng),
+        RotationMode::Weighted => {
+            let weights = sounds.iter().map(|sound| sound.weight.max(0));
+            match WeightedIndex::new(weights) {
+                Ok(index) => sounds.get(index.sample(&mut rng)),
+                // Every weight is zero, so fall back to an even chance
+                Err(_) => sounds.choose(&mut rng),
+            }
+        }
+        // Sounds that have never played come first, then the one that has
+        // waited the longest.
+        RotationMode::RoundRobin => sounds
+            .iter()
+            .min_by_key(|sound| (sound.last_played, sound.id)),
+        RotationMode::Shuffle => {
+            let unplayed = sounds
+                .iter()
+                .filter(|sound| !sound.played_in_cycle)
+                .collect::<Vec<_>>();
+            if unplayed.is_empty() {
+                sounds.choose(&mut rng)
+            } else {
+                unplayed.choose(&mut rng).copied()
+            }
+        }
+    }
+}
+
+/// Remember that `played` was picked from `sounds`, starting a new shuffle
+/// cycle once every sound in the set has played.
+pub fn mark_played(sounds: &[SoundEntry], played: &SoundEntry) {
+    let connection = &mut connect();
+    let cycle_finished = sounds
+        .iter()
+        .all(|sound| sound.id == played.id || sound.played_in_cycle);
+    if cycle_finished {
+        let ids = sounds.iter().map(|sound| sound.id).collect::<Vec<_>>();
+        diesel::update(joinsounds::table)
+            .filter(joinsounds::id.eq_any(ids))
+            .set(joinsounds::played_in_cycle.eq(false))
+            .execute(connection)
+            .expect("Error resetting shuffle");
+    }
+    // A single sound would finish every cycle, so leave it unplayed
+    if !cycle_finished || sounds.len() > 1 {
+        diesel::update(joinsounds::table.find(played.id))
+            .set(joinsounds::played_in_cycle.eq(true))
+            .execute(connection)
+            .expect("Error marking sound as played");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sound(id: i32, weight: i32, last_played: Option<i64>) -> SoundEntry {
+        SoundEntry {
+            id,
+            file_path: None,
+            last_played: last_played.map(|seconds| {
+                chrono::DateTime::from_timestamp(seconds, 0)
+                    .unwrap()
+                    .naive_utc()
+            }),
+            weight,
+            played_in_cycle: false,
+            library_sound_id: None,
+            starts_at: None,
+            ends_at: None,
+            time_start: None,
+            time_end: None,
+            weekdays: None,
+        }
+    }
+
+    #[test]
+    fn nothing_to_pick_from_an_empty_set() {
+        for mode in [
+            RotationMode::Random,
+            RotationMode::Weighted,
+            RotationMode::RoundRobin,
+            RotationMode::Shuffle,
+        ] {
+            assert!(pick_sound(mode, &[]).is_none());
+        }
+    }
+
+    #[test]
+    fn round_robin_plays_unplayed_sounds_then_the_oldest() {
+        let sounds = [
+            sound(1, 1, Some(300)),
+            sound(2, 1, None),
+            sound(3, 1, Some(100)),
+            sound(4, 1, None),
+        ];
+        let pick = |sounds: &[SoundEntry]| pick_sound(RotationMode::RoundRobin, sounds).unwrap().id;
+        assert_eq!(pick(&sounds), 2);
+        assert_eq!(pick(&sounds[2..]), 4);
+        assert_eq!(pick(&[sounds[0].clone(), sounds[2].clone()]), 3);
+    }
+
+    #[test]
+    fn weighted_only_picks_sounds_with_weight() {
+        let sounds = [sound(1, 0, None), sound(2, 3, None), sound(3, 0, None)];
+        for _ in 0..50 {
+            assert_eq!(pick_sound(RotationMode::Weighted, &sounds).unwrap().id, 2);
+        }
+    }
+
+    #[test]
+    fn weighted_falls_back_to_any_sound_when_every_weight_is_zero() {
+        let sounds = [sound(1, 0, None), sound(2, -1, None)];
+        for _ in 0..50 {
+            assert!(pick_sound(RotationMode::Weighted, &sounds).is_some());
+        }
+    }
+
+    #[test]
+    fn shuffle_plays_every_sound_once_per_cycle() {
+        let mut sounds = (1..=5).map(|id| sound(id, 1, None)).collect::<Vec<_>>();
+        let mut played = vec![];
+        for _ in 0..sounds.len() {
+            let id = pick_sound(RotationMode::Shuffle, &sounds).unwrap().id;
+            assert!(!played.contains(&id));
+            played.push(id);
+            for sound in sounds.iter_mut().filter(|sound| sound.id == id) {
+                sound.played_in_cycle = true;
+            }
+        }
+        assert_eq!(played.len(), sounds.len());
+        // A finished cycle still has something to play
+        assert!(pick_sound(RotationMode::Shuffle, &sounds).is_some());
+    }
+}
@@ -5,5 +5,155 @@ table! {
         guild_id -> Nullable<Varchar>,
         file_path -> Nullable<Varchar>,
         last_played -> Nullable<Timestamp>,
+        kind -> Varchar,
+        weight -> Integer,
+        played_in_cycle -> Bool,
+        library_sound_id -> Nullable<Integer>,
+        starts_at -> Nullable<Timestamp>,
+        ends_at -> Nullable<Timestamp>,
+        time_start -> Nullable<Time>,
+        time_end -> Nullable<Time>,
+        weekdays -> Nullable<Integer>,
+        channel_id -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    guild_settings (guild_id) {
+        guild_id -> Varchar,
+        max_queue_length -> Integer,
+        queue_drop_policy -> Varchar,
+        cooldown_seconds -> Integer,
+        idle_timeout_minutes -> Integer,
+        stay_while_occupied -> Bool,
+        play_on_join -> Bool,
+        move_policy -> Varchar,
+        busy_policy -> Varchar,
+        ignore_afk_channel -> Bool,
+        ignore_deafened -> Bool,
+        ignore_muted -> Bool,
+        min_other_members -> Integer,
+        timezone -> Varchar,
+        paused_until -> Nullable<Timestamp>,
+        theme_policy -> Varchar,
+        play_on_stream -> Bool,
+        play_on_camera -> Bool,
+        play_on_unmute -> Bool,
+        group_window_seconds -> Integer,
+        group_sound_path -> Nullable<Varchar>,
+        soundboard_role_id -> Nullable<Varchar>,
+        stop_role_id -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    member_cooldowns (guild_id, discord_id) {
+        guild_id -> Varchar,
+        discord_id -> Varchar,
+        cooldown_seconds -> Nullable<Integer>,
+        last_triggered -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    move_triggers (id) {
+        id -> Integer,
+        guild_id -> Varchar,
+        from_channel_id -> Nullable<Varchar>,
+        to_channel_id -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    channel_filters (id) {
+        id -> Integer,
+        guild_id -> Varchar,
+        channel_id -> Varchar,
+        list -> Varchar,
+    }
+}
+
+table! {
+    quiet_hours (id) {
+        id -> Integer,
+        guild_id -> Varchar,
+        start_time -> Time,
+        end_time -> Time,
+    }
+}
+
+table! {
+    rotation_modes (id) {
+        id -> Integer,
+        discord_id -> Varchar,
+        guild_id -> Nullable<Varchar>,
+        kind -> Varchar,
+        mode -> Varchar,
+        channel_id -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    library_sounds (id) {
+        id -> Integer,
+        discord_id -> Varchar,
+        name -> Varchar,
+        file_path -> Varchar,
+    }
+}
+
+table! {
+    user_settings (discord_id) {
+        discord_id -> Varchar,
+        timezone -> Varchar,
+    }
+}
+
+table! {
+    channel_themes (guild_id, channel_id) {
+        guild_id -> Varchar,
+        channel_id -> Varchar,
+        file_path -> Varchar,
+        first_join_only -> Bool,
+    }
+}
+
+table! {
+    guild_default_sounds (guild_id) {
+        guild_id -> Varchar,
+        file_path -> Varchar,
+        first_join_only -> Bool,
+    }
+}
+
+table! {
+    voice_members (guild_id, discord_id) {
+        guild_id -> Varchar,
+        discord_id -> Varchar,
+        first_joined_at -> Timestamp,
+    }
+}
+
+table! {
+    soundboard_sounds (id) {
+        id -> Integer,
+        guild_id -> Varchar,
+        name -> Varchar,
+        file_path -> Varchar,
+        added_by -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    play_history (id) {
+        id -> Integer,
+        guild_id -> Varchar,
+        channel_id -> Varchar,
+        discord_id -> Varchar,
+        source -> Varchar,
+        sound -> Nullable<Varchar>,
+        outcome -> Varchar,
+        detail -> Nullable<Varchar>,
+        played_at -> Timestamp,
     }
 }
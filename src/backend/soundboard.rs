@@ -0,0 +1,96 @@
+use diesel::prelude::*;
+use poise::serenity_prelude as serenity;
+use std::path::PathBuf;
+
+use super::attachments::{self, PendingSound};
+use super::database::connect;
+use super::file;
+use super::models::SoundboardSound;
+use super::schema::soundboard_sounds;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// How many sounds a guild can keep on its soundboard, which is also as many
+/// buttons as fit on one panel.
+pub const MAX_SOUNDBOARD_SOUNDS: usize = 25;
+
+pub fn list_soundboard_sounds(guild: serenity::GuildId) -> Vec<SoundboardSound> {
+    let connection = &mut connect();
+    soundboard_sounds::table
+        .filter(soundboard_sounds::guild_id.eq(guild.to_string()))
+        .select(SoundboardSound::as_select())
+        .order(soundboard_sounds::name)
+        .load(connection)
+        .unwrap_or_default()
+}
+
+pub fn get_soundboard_sound(guild: serenity::GuildId, name: &str) -> Option<SoundboardSound> {
+    let connection = &mut connect();
+    soundboard_sounds::table
+        .filter(soundboard_sounds::guild_id.eq(guild.to_string()))
+        .filter(soundboard_sounds::name.eq(name))
+        .select(SoundboardSound::as_select())
+        .first(connection)
+        .ok()
+}
+
+pub async fn get_soundboard_file(sound: &SoundboardSound) -> Result<PathBuf, Error> {
+    Ok(file::canonicalize_file_path(PathBuf::from(&sound.file_path)).await?)
+}
+
+/// Store a pending sound on the guild's soundboard under `name`.
+pub async fn add_soundboard_sound(
+    guild: serenity::GuildId,
+    user: serenity::UserId,
+    sound: &PendingSound,
+    name: &str,
+) -> Result<(), Error> {
+    if get_soundboard_sound(guild, name).is_some() {
+        return Err(Box::new(std::io::Error::other(format!(
+            "The soundboard already has a sound called {name}."
+        ))));
+    }
+    if list_soundboard_sounds(guild).len() >= MAX_SOUNDBOARD_SOUNDS {
+        return Err(Box::new(std::io::Error::other(format!(
+            "The soundboard already has {MAX_SOUNDBOARD_SOUNDS} sounds, remove one first."
+        ))));
+    }
+    let file_path = attachments::store_guild_sound(sound, guild, "soundboard").await?;
+    let connection = &mut connect();
+    diesel::insert_into(soundboard_sounds::table)
+        .values((
+            soundboard_sounds::guild_id.eq(guild.to_string()),
+            soundboard_sounds::name.eq(name),
+            soundboard_sounds::file_path.eq(&file_path),
+            soundboard_sounds::added_by.eq(user.to_string()),
+        ))
+        .execute(connection)
+        .expect("Error saving soundboard sound");
+    attachments::discard_sound(sound).await;
+    Ok(())
+}
+
+pub async fn remove_soundboard_sound(guild: serenity::GuildId, name: &str) -> Result<(), Error> {
+    let Some(sound) = get_soundboard_sound(guild, name) else {
+        return Err(Box::new(std::io::Error::other(format!(
+            "The soundboard doesn't have a sound called {name}."
+        ))));
+    };
+    file::delete_file_if_exists(PathBuf::from(&sound.file_path)).await?;
+    let connection = &mut connect();
+    diesel::delete(soundboard_sounds::table.find(sound.id))
+        .execute(connection)
+        .expect("Error removing soundboard sound");
+    Ok(())
+}
+
+/// Keep the sounds a user added to soundboards, since they belong to the
+/// guild, but forget who added them.
+pub fn forget_uploader(user: serenity::UserId) {
+    let connection = &mut connect();
+    diesel::update(soundboard_sounds::table)
+        .filter(soundboard_sounds::added_by.eq(user.to_string()))
+        .set(soundboard_sounds::added_by.eq(None::<String>))
+        .execute(connection)
+        .expect("Error forgetting soundboard uploader");
+}
@@ -0,0 +1,166 @@
+use chrono::{Offset, TimeZone};
+use chrono_tz::Tz;
+use diesel::dsl::{count_star, sql};
+use diesel::prelude::*;
+use diesel::sql_types::BigInt;
+use poise::serenity_prelude as serenity;
+
+use super::database::connect;
+use super::history::{PlayOutcome, PlaySource};
+use super::schema::{joinsounds, library_sounds, play_history, soundboard_sounds};
+use super::SoundKind;
+
+/// Count the sounds that played, optionally only in one guild or for one user.
+pub fn count_plays(guild: Option<serenity::GuildId>, user: Option<serenity::UserId>) -> i64 {
+    let connection = &mut connect();
+    let mut query = play_history::table
+        .filter(play_history::outcome.eq(PlayOutcome::Played.as_str()))
+        .select(count_star())
+        .into_boxed();
+    if let Some(guild) = guild {
+        query = query.filter(play_history::guild_id.eq(guild.to_string()));
+    }
+    if let Some(user) = user {
+        query = query.filter(play_history::discord_id.eq(user.to_string()));
+    }
+    query.first(connection).unwrap_or(0)
+}
+
+/// The sources of plays that were a member's own sound, rather than a theme,
+/// the soundboard or a sound shared by the guild.
+fn member_sources() -> Vec<&'static str> {
+    let mut sources = [
+        SoundKind::Join,
+        SoundKind::Leave,
+        SoundKind::Stream,
+        SoundKind::Camera,
+        SoundKind::Unmute,
+    ]
+    .iter()
+    .map(|kind| PlaySource::Member(*kind).as_str())
+    .collect::<Vec<_>>();
+    sources.push(PlaySource::OnDemand.as_str());
+    sources
+}
+
+/// Get the members whose own sounds played most in a guild, with their play
+/// counts.
+pub fn most_heard_members(guild: serenity::GuildId, limit: i64) -> Vec<(String, i64)> {
+    let connection = &mut connect();
+    play_history::table
+        .filter(play_history::guild_id.eq(guild.to_string()))
+        .filter(play_history::outcome.eq(PlayOutcome::Played.as_str()))
+        .filter(play_history::source.eq_any(member_sources()))
+        .group_by(play_history::discord_id)
+        .select((play_history::discord_id, count_star()))
+        .order(count_star().desc())
+        .limit(limit)
+        .load(connection)
+        .unwrap_or_default()
+}
+
+/// Count the sounds that played in each hour of the day in `timezone`,
+/// optionally only in one guild or for one user. Plays are shifted by the
+/// timezone's current offset, so ones from before a daylight saving change
+/// land an hour off.
+pub fn plays_by_hour(
+    guild: Option<serenity::GuildId>,
+    user: Option<serenity::UserId>,
+    timezone: Tz,
+) -> [i64; 24] {
+    let connection = &mut connect();
+    let offset = timezone
+        .offset_from_utc_datetime(&chrono::Utc::now().naive_utc())
+        .fix()
+        .local_minus_utc();
+    let hour = sql::<BigInt>(&format!(
+        "HOUR(CONVERT_TZ(played_at, '+00:00', '{}{:02}:{:02}'))",
+        if offset < 0 { '-' } else { '+' },
+        offset.abs() / 3600,
+        offset.abs() % 3600 / 60
+    ));
+    let mut query = play_history::table
+        .filter(play_history::outcome.eq(PlayOutcome::Played.as_str()))
+        .group_by(hour.clone())
+        .select((hour, count_star()))
+        .into_boxed();
+    if let Some(guild) = guild {
+        query = query.filter(play_history::guild_id.eq(guild.to_string()));
+    }
+    if let Some(user) = user {
+        query = query.filter(play_history::discord_id.eq(user.to_string()));
+    }
+    let mut hours = [0; 24];
+    for (hour, plays) in query.load::<(i64, i64)>(connection).unwrap_or_default() {
+        if let Some(count) = hours.get_mut(hour as usize) {
+            *count = plays;
+        }
+    }
+    hours
+}
+
+/// Count the sounds a user has stored, including their library.
+pub fn count_user_sounds(user: serenity::UserId) -> i64 {
+    let connection = &mut connect();
+    let sounds: i64 = joinsounds::table
+        .filter(joinsounds::discord_id.eq(user.to_string()))
+        .filter(joinsounds::library_sound_id.is_null())
+        .count()
+        .get_result(connection)
+        .unwrap_or(0);
+    let library: i64 = library_sounds::table
+        .filter(library_sounds::discord_id.eq(user.to_string()))
+        .count()
+        .get_result(connection)
+        .unwrap_or(0);
+    sounds + library
+}
+
+/// Count the sounds stored for a guild, both members' local sounds and the
+/// soundboard.
+pub fn count_guild_sounds(guild: serenity::GuildId) -> i64 {
+    let connection = &mut connect();
+    let sounds: i64 = joinsounds::table
+        .filter(joinsounds::guild_id.eq(guild.to_string()))
+        .count()
+        .get_result(connection)
+        .unwrap_or(0);
+    let soundboard: i64 = soundboard_sounds::table
+        .filter(soundboard_sounds::guild_id.eq(guild.to_string()))
+        .count()
+        .get_result(connection)
+        .unwrap_or(0);
+    sounds + soundboard
+}
+
+/// Count every sound file stored by the bot.
+pub fn count_all_sounds() -> i64 {
+    let connection = &mut connect();
+    let sounds: i64 = joinsounds::table
+        .filter(joinsounds::library_sound_id.is_null())
+        .count()
+        .get_result(connection)
+        .unwrap_or(0);
+    let library: i64 = library_sounds::table
+        .count()
+        .get_result(connection)
+        .unwrap_or(0);
+    let soundboard: i64 = soundboard_sounds::table
+        .count()
+        .get_result(connection)
+        .unwrap_or(0);
+    sounds + library + soundboard
+}
+
+/// Count the guilds and members that have played sounds.
+pub fn count_active_guilds_and_users() -> (i64, i64) {
+    let connection = &mut connect();
+    play_history::table
+        .filter(play_history::outcome.eq(PlayOutcome::Played.as_str()))
+        .select((
+            sql::<BigInt>("COUNT(DISTINCT guild_id)"),
+            sql::<BigInt>("COUNT(DISTINCT discord_id)"),
+        ))
+        .first(connection)
+        .unwrap_or((0, 0))
+}
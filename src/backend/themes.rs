@@ -0,0 +1,83 @@
+use diesel::prelude::*;
+use poise::serenity_prelude as serenity;
+use std::path::PathBuf;
+use tracing::warn;
+
+use super::attachments::{self, PendingSound};
+use super::database::connect;
+use super::file;
+use super::models::ChannelTheme;
+use super::schema::channel_themes;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+pub fn get_theme(guild: serenity::GuildId, channel: serenity::ChannelId) -> Option<ChannelTheme> {
+    let connection = &mut connect();
+    channel_themes::table
+        .filter(channel_themes::guild_id.eq(guild.to_string()))
+        .filter(channel_themes::channel_id.eq(channel.to_string()))
+        .select(ChannelTheme::as_select())
+        .first(connection)
+        .ok()
+}
+
+/// Get every channel with a theme in a guild.
+pub fn list_themes(guild: serenity::GuildId) -> Vec<ChannelTheme> {
+    let connection = &mut connect();
+    channel_themes::table
+        .filter(channel_themes::guild_id.eq(guild.to_string()))
+        .select(ChannelTheme::as_select())
+        .load(connection)
+        .unwrap_or_default()
+}
+
+pub async fn get_theme_file(theme: &ChannelTheme) -> Result<PathBuf, Error> {
+    Ok(file::canonicalize_file_path(PathBuf::from(&theme.file_path)).await?)
+}
+
+/// Attach a pending sound to a voice channel, replacing any theme it had.
+pub async fn set_theme(
+    guild: serenity::GuildId,
+    channel: serenity::ChannelId,
+    sound: &PendingSound,
+    first_join_only: bool,
+) -> Result<(), Error> {
+    let old_theme = get_theme(guild, channel);
+    let file_path = attachments::store_guild_sound(sound, guild, "themes").await?;
+    let connection = &mut connect();
+    diesel::replace_into(channel_themes::table)
+        .values((
+            channel_themes::guild_id.eq(guild.to_string()),
+            channel_themes::channel_id.eq(channel.to_string()),
+            channel_themes::file_path.eq(file_path),
+            channel_themes::first_join_only.eq(first_join_only),
+        ))
+        .execute(connection)
+        .expect("Error saving channel theme");
+    attachments::discard_sound(sound).await;
+    if let Some(old_theme) = old_theme {
+        if let Err(why) = file::delete_file_if_exists(PathBuf::from(&old_theme.file_path)).await {
+            warn!("Error deleting old channel theme: {why}");
+        }
+    }
+    Ok(())
+}
+
+pub async fn remove_theme(
+    guild: serenity::GuildId,
+    channel: serenity::ChannelId,
+) -> Result<(), Error> {
+    let Some(theme) = get_theme(guild, channel) else {
+        return Err(Box::new(std::io::Error::other(
+            "This channel has no theme!",
+        )));
+    };
+    file::delete_file_if_exists(PathBuf::from(&theme.file_path)).await?;
+    let connection = &mut connect();
+    diesel::delete(channel_themes::table)
+        .filter(channel_themes::guild_id.eq(guild.to_string()))
+        .filter(channel_themes::channel_id.eq(channel.to_string()))
+        .execute(connection)
+        .expect("Error removing channel theme");
+    Ok(())
+}
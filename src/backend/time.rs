@@ -0,0 +1,47 @@
+use chrono::NaiveTime;
+
+/// Whether `time` falls in the window from `start` to `end`. Windows where
+/// `end` is before `start` wrap around midnight.
+pub fn in_time_window(time: NaiveTime, start: NaiveTime, end: NaiveTime) -> bool {
+    if start <= end {
+        start <= time && time < end
+    } else {
+        time >= start || time < end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn window_includes_start_and_excludes_end() {
+        let (start, end) = (time(9, 0), time(17, 0));
+        assert!(in_time_window(time(9, 0), start, end));
+        assert!(in_time_window(time(12, 30), start, end));
+        assert!(!in_time_window(time(17, 0), start, end));
+        assert!(!in_time_window(time(8, 59), start, end));
+    }
+
+    #[test]
+    fn window_wraps_past_midnight() {
+        let (start, end) = (time(22, 0), time(2, 0));
+        assert!(in_time_window(time(22, 0), start, end));
+        assert!(in_time_window(time(23, 59), start, end));
+        assert!(in_time_window(time(0, 0), start, end));
+        assert!(in_time_window(time(1, 59), start, end));
+        assert!(!in_time_window(time(2, 0), start, end));
+        assert!(!in_time_window(time(12, 0), start, end));
+    }
+
+    #[test]
+    fn window_with_equal_bounds_is_empty() {
+        let bound = time(8, 0);
+        assert!(!in_time_window(time(8, 0), bound, bound));
+        assert!(!in_time_window(time(20, 0), bound, bound));
+    }
+}
@@ -0,0 +1,39 @@
+use chrono_tz::Tz;
+use diesel::prelude::*;
+use poise::serenity_prelude as serenity;
+
+use super::database::connect;
+use super::schema::user_settings;
+
+/// Get the timezone a user's time of day sounds are matched in.
+pub fn get_timezone(user: serenity::UserId) -> Tz {
+    let connection = &mut connect();
+    user_settings::table
+        .filter(user_settings::discord_id.eq(user.to_string()))
+        .select(user_settings::timezone)
+        .first::<String>(connection)
+        .ok()
+        .and_then(|timezone| timezone.parse().ok())
+        .unwrap_or(Tz::UTC)
+}
+
+pub fn set_timezone(user: serenity::UserId, timezone: Tz) {
+    let connection = &mut connect();
+    diesel::insert_or_ignore_into(user_settings::table)
+        .values(user_settings::discord_id.eq(user.to_string()))
+        .execute(connection)
+        .expect("Error creating user settings");
+    diesel::update(user_settings::table)
+        .filter(user_settings::discord_id.eq(user.to_string()))
+        .set(user_settings::timezone.eq(timezone.name()))
+        .execute(connection)
+        .expect("Error setting timezone");
+}
+
+pub fn remove_user_settings(user: serenity::UserId) {
+    let connection = &mut connect();
+    diesel::delete(user_settings::table)
+        .filter(user_settings::discord_id.eq(user.to_string()))
+        .execute(connection)
+        .expect("Error removing user settings");
+}
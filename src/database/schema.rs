@@ -10,5 +10,220 @@ diesel::table! {
         #[max_length = 255]
         file_path -> Nullable<Varchar>,
         last_played -> Timestamp,
+        #[max_length = 32]
+        kind -> Varchar,
+        weight -> Integer,
+        played_in_cycle -> Bool,
+        library_sound_id -> Nullable<Integer>,
+        starts_at -> Nullable<Timestamp>,
+        ends_at -> Nullable<Timestamp>,
+        time_start -> Nullable<Time>,
+        time_end -> Nullable<Time>,
+        weekdays -> Nullable<Integer>,
+        #[max_length = 255]
+        channel_id -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    channel_filters (id) {
+        id -> Integer,
+        #[max_length = 255]
+        guild_id -> Varchar,
+        #[max_length = 255]
+        channel_id -> Varchar,
+        #[max_length = 32]
+        list -> Varchar,
+    }
+}
+
+diesel::table! {
+    channel_themes (guild_id, channel_id) {
+        #[max_length = 255]
+        guild_id -> Varchar,
+        #[max_length = 255]
+        channel_id -> Varchar,
+        #[max_length = 255]
+        file_path -> Varchar,
+        first_join_only -> Bool,
+    }
+}
+
+diesel::table! {
+    guild_default_sounds (guild_id) {
+        #[max_length = 255]
+        guild_id -> Varchar,
+        #[max_length = 255]
+        file_path -> Varchar,
+        first_join_only -> Bool,
+    }
+}
+
+diesel::table! {
+    guild_settings (guild_id) {
+        #[max_length = 255]
+        guild_id -> Varchar,
+        max_queue_length -> Integer,
+        #[max_length = 32]
+        queue_drop_policy -> Varchar,
+        cooldown_seconds -> Integer,
+        idle_timeout_minutes -> Integer,
+        stay_while_occupied -> Bool,
+        play_on_join -> Bool,
+        #[max_length = 32]
+        move_policy -> Varchar,
+        #[max_length = 32]
+        busy_policy -> Varchar,
+        ignore_afk_channel -> Bool,
+        ignore_deafened -> Bool,
+        ignore_muted -> Bool,
+        min_other_members -> Integer,
+        #[max_length = 64]
+        timezone -> Varchar,
+        paused_until -> Nullable<Timestamp>,
+        #[max_length = 32]
+        theme_policy -> Varchar,
+        play_on_stream -> Bool,
+        play_on_camera -> Bool,
+        play_on_unmute -> Bool,
+        group_window_seconds -> Integer,
+        #[max_length = 255]
+        group_sound_path -> Nullable<Varchar>,
+        #[max_length = 255]
+        soundboard_role_id -> Nullable<Varchar>,
+        #[max_length = 255]
+        stop_role_id -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    library_sounds (id) {
+        id -> Integer,
+        #[max_length = 255]
+        discord_id -> Varchar,
+        #[max_length = 100]
+        name -> Varchar,
+        #[max_length = 255]
+        file_path -> Varchar,
     }
 }
+
+diesel::table! {
+    member_cooldowns (guild_id, discord_id) {
+        #[max_length = 255]
+        guild_id -> Varchar,
+        #[max_length = 255]
+        discord_id -> Varchar,
+        cooldown_seconds -> Nullable<Integer>,
+        last_triggered -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    move_triggers (id) {
+        id -> Integer,
+        #[max_length = 255]
+        guild_id -> Varchar,
+        #[max_length = 255]
+        from_channel_id -> Nullable<Varchar>,
+        #[max_length = 255]
+        to_channel_id -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    play_history (id) {
+        id -> Integer,
+        #[max_length = 255]
+        guild_id -> Varchar,
+        #[max_length = 255]
+        channel_id -> Varchar,
+        #[max_length = 255]
+        discord_id -> Varchar,
+        #[max_length = 32]
+        source -> Varchar,
+        #[max_length = 255]
+        sound -> Nullable<Varchar>,
+        #[max_length = 32]
+        outcome -> Varchar,
+        #[max_length = 255]
+        detail -> Nullable<Varchar>,
+        played_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    quiet_hours (id) {
+        id -> Integer,
+        #[max_length = 255]
+        guild_id -> Varchar,
+        start_time -> Time,
+        end_time -> Time,
+    }
+}
+
+diesel::table! {
+    rotation_modes (id) {
+        id -> Integer,
+        #[max_length = 255]
+        discord_id -> Varchar,
+        #[max_length = 255]
+        guild_id -> Nullable<Varchar>,
+        #[max_length = 32]
+        kind -> Varchar,
+        #[max_length = 32]
+        mode -> Varchar,
+        #[max_length = 255]
+        channel_id -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    soundboard_sounds (id) {
+        id -> Integer,
+        #[max_length = 255]
+        guild_id -> Varchar,
+        #[max_length = 100]
+        name -> Varchar,
+        #[max_length = 255]
+        file_path -> Varchar,
+        #[max_length = 255]
+        added_by -> Nullable<Varchar>,
+    }
+}
+
+diesel::table! {
+    user_settings (discord_id) {
+        #[max_length = 255]
+        discord_id -> Varchar,
+        #[max_length = 64]
+        timezone -> Varchar,
+    }
+}
+
+diesel::table! {
+    voice_members (guild_id, discord_id) {
+        #[max_length = 255]
+        guild_id -> Varchar,
+        #[max_length = 255]
+        discord_id -> Varchar,
+        first_joined_at -> Timestamp,
+    }
+}
+
+diesel::allow_tables_to_appear_in_same_query!(
+    channel_filters,
+    channel_themes,
+    guild_default_sounds,
+    guild_settings,
+    joinsounds,
+    library_sounds,
+    member_cooldowns,
+    move_triggers,
+    play_history,
+    quiet_hours,
+    rotation_modes,
+    soundboard_sounds,
+    user_settings,
+    voice_members,
+);
@@ -1,12 +1,17 @@
-use serenity::async_trait;
-use serenity::prelude::Mutex;
-use songbird::{
-    tracks::Track, Call, EventContext as SongbirdEventContext, EventHandler as SongbirdEventHandler,
-};
+use poise::serenity_prelude::{ChannelId, GuildId, VoiceState};
+use serenity::prelude::{Mutex, TypeMapKey};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tracing::{error, info, instrument, span, warn, Level};
+use std::time::Duration;
+use tracing::{error, info, span, warn, Level};
 
 use super::backend;
+use super::backend::guild_settings::ThemePolicy;
+use super::backend::history::{PlayOutcome, PlaySource};
+use super::backend::models::GuildSettings;
+use super::backend::SoundKind;
+use super::playback::{self, QueuedSound};
 
 type Data = ();
 type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -24,92 +29,73 @@ pub async fn event_listener(
         poise::serenity_prelude::FullEvent::VoiceStateUpdate { old, new } => {
             let span = span!(Level::INFO, "voice_state_update", event=%event.snake_case_name());
             let _enter = span.enter();
-            if old.is_none() {
-                info!(
-                    "{:?} joined voice channel in {:?}",
-                    new.user_id, new.guild_id
-                );
-                if let Some(guild_id) = new.guild_id {
-                    let has_local_sound = backend::has_sound(new.user_id, Some(guild_id));
-                    let has_global_sound = backend::has_sound(new.user_id, None);
-                    if has_local_sound || has_global_sound {
-                        let last_played_local_option =
-                            backend::get_last_played(new.user_id, Some(guild_id));
-                        let last_played_global_option = backend::get_last_played(new.user_id, None);
-                        let last_played =
-                            match (last_played_local_option, last_played_global_option) {
-                                (Some(last_played_local), Some(last_played_global)) => {
-                                    if last_played_local > last_played_global {
-                                        Some(last_played_local)
-                                    } else {
-                                        Some(last_played_global)
-                                    }
-                                }
-                                (Some(last_played_local), None) => Some(last_played_local),
-                                (None, Some(last_played_global)) => Some(last_played_global),
-                                (None, None) => None,
-                            };
-
-                        if let Some(last_played) = last_played {
-                            if chrono::Utc::now().naive_utc().and_utc().timestamp()
-                                - last_played.and_utc().timestamp()
-                                < 30
-                            {
-                                warn!("Too soon to play sound.");
-                                return Ok(());
-                            }
-                        }
-
-                        let manager = songbird::get(ctx)
-                            .await
-                            .expect("Songbird Voice client placed in at initialisation.")
-                            .clone();
-
-                        let mut connect = false;
-                        if let Some(handler_mutex) = manager.get(guild_id) {
-                            let handler = handler_mutex.lock().await;
-
-                            if handler.current_connection().is_none() {
-                                connect = true;
-                            }
-                        } else {
-                            connect = true;
-                        }
-                        if connect {
-                            if let Some(voice_channel) = new.channel_id {
-                                let _handler = manager.join(guild_id, voice_channel).await;
-                            } else {
-                                error!("could not find voice channel");
-                                return Ok(());
-                            }
-                        }
-
-                        if let Some(handler_lock) = manager.get(guild_id) {
-                            let songbird_file =
-                                match backend::get_sound(new.user_id, guild_id).await {
-                                    Ok(joinsound) => songbird::input::File::new(joinsound),
-                                    Err(_) => {
-                                        error!("no joinsound");
-                                        return Ok(());
-                                    }
-                                };
-                            let track = Track::from(songbird_file);
-                            let mut handler = handler_lock.lock().await;
-                            let track_handler = handler.play_only(track);
-
-                            if let Some(call) = manager.get(guild_id) {
-                                if let Err(why) = track_handler.add_event(
-                                    songbird::events::Event::Track(
-                                        songbird::events::TrackEvent::End,
-                                    ),
-                                    SongEndNotifier { call },
-                                ) {
-                                    error!("Cannot add event: {}", why);
-                                }
-                            }
-                        };
+            let Some(guild_id) = new.guild_id else {
+                return Ok(());
+            };
+            let settings = backend::guild_settings::get_guild_settings(guild_id);
+            let old_channel = old.as_ref().and_then(|old| old.channel_id);
+            // A new session in the same channel means the cached state is stale
+            // and the member actually reconnected.
+            let new_session = old
+                .as_ref()
+                .is_none_or(|old| old.session_id != new.session_id);
+            match (old_channel, new.channel_id) {
+                (None, Some(channel_id)) => {
+                    info!("{:?} joined voice channel in {:?}", new.user_id, guild_id);
+                    let first_join =
+                        backend::default_sound::record_voice_join(guild_id, new.user_id);
+                    if settings.play_on_join {
+                        greet_member(ctx, &settings, guild_id, new, channel_id, first_join).await;
+                    }
+                }
+                (Some(from), Some(to)) if from == to && new_session => {
+                    info!("{:?} rejoined voice channel in {:?}", new.user_id, guild_id);
+                    let first_join =
+                        backend::default_sound::record_voice_join(guild_id, new.user_id);
+                    if settings.play_on_join {
+                        greet_member(ctx, &settings, guild_id, new, to, first_join).await;
+                    }
+                }
+                (Some(from), Some(to)) if from != to => {
+                    info!("{:?} moved voice channel in {:?}", new.user_id, guild_id);
+                    // The member may have joined while the bot was offline
+                    let first_join =
+                        backend::default_sound::record_voice_join(guild_id, new.user_id);
+                    if backend::guild_settings::should_play_on_move(guild_id, &settings, from, to) {
+                        greet_member(ctx, &settings, guild_id, new, to, first_join).await;
+                    }
+                }
+                (Some(from), Some(to)) if from == to => {
+                    let Some(old) = old else {
+                        return Ok(());
+                    };
+                    if let Some(kind) = voice_state_trigger(&settings, old, new) {
+                        info!(
+                            "{:?} triggered a {} sound in {:?}",
+                            new.user_id,
+                            kind.as_str(),
+                            guild_id
+                        );
+                        play_member_sound(ctx, &settings, guild_id, new, to, kind, false).await;
+                    }
+                }
+                (Some(from), None) => {
+                    info!("{:?} left voice channel in {:?}", new.user_id, guild_id);
+                    // Only go back to the channel if someone is left to hear it
+                    if playback::humans_in_channel(ctx, guild_id, from) > 0 {
+                        play_member_sound(
+                            ctx,
+                            &settings,
+                            guild_id,
+                            new,
+                            from,
+                            SoundKind::Leave,
+                            false,
+                        )
+                        .await;
                     }
                 }
+                _ => {}
             }
         }
         _ => {}
@@ -118,21 +104,361 @@ pub async fn event_listener(
     Ok(())
 }
 
-#[derive(Debug)]
-struct SongEndNotifier {
-    call: Arc<Mutex<Call>>,
+/// Members who joined a channel recently and are waiting to be greeted
+/// together, counted by channel.
+pub struct PendingArrivals;
+
+impl TypeMapKey for PendingArrivals {
+    type Value = Arc<Mutex<HashMap<(GuildId, ChannelId), usize>>>;
+}
+
+/// Play the join sound for a member arriving in `channel_id`. When the guild
+/// groups arrivals, the first member to arrive waits for the window to close
+/// and greets everyone who joined in the meantime with one sound.
+/// `first_join` is whether this is the member's first time in voice here.
+async fn greet_member(
+    ctx: &serenity::client::Context,
+    settings: &GuildSettings,
+    guild_id: GuildId,
+    voice_state: &VoiceState,
+    channel_id: ChannelId,
+    first_join: bool,
+) {
+    if settings.group_window_seconds <= 0 {
+        play_member_sound(
+            ctx,
+            settings,
+            guild_id,
+            voice_state,
+            channel_id,
+            SoundKind::Join,
+            first_join,
+        )
+        .await;
+        return;
+    }
+
+    let arrivals = ctx
+        .data
+        .read()
+        .await
+        .get::<PendingArrivals>()
+        .cloned()
+        .expect("Pending arrivals placed in at initialisation.");
+    {
+        let mut arrivals = arrivals.lock().await;
+        if let Some(count) = arrivals.get_mut(&(guild_id, channel_id)) {
+            info!("Adding {:?} to a group arrival", voice_state.user_id);
+            *count += 1;
+            return;
+        }
+        arrivals.insert((guild_id, channel_id), 1);
+    }
+
+    let ctx = ctx.clone();
+    let settings = settings.clone();
+    let user_id = voice_state.user_id;
+    let window = Duration::from_secs(settings.group_window_seconds as u64);
+    tokio::spawn(async move {
+        tokio::time::sleep(window).await;
+        let count = arrivals
+            .lock()
+            .await
+            .remove(&(guild_id, channel_id))
+            .unwrap_or(1);
+        // The first member might have left or moved while waiting
+        let Some(voice_state) = ctx
+            .cache
+            .guild(guild_id)
+            .and_then(|guild| guild.voice_states.get(&user_id).cloned())
+            .filter(|voice_state| voice_state.channel_id == Some(channel_id))
+        else {
+            return;
+        };
+        let group_sound_path = settings.group_sound_path.clone().filter(|_| count > 1);
+        match group_sound_path {
+            Some(path) => {
+                info!("Greeting a group of {} members", count);
+                play_group_sound(&ctx, &settings, guild_id, &voice_state, channel_id, path).await;
+            }
+            None => {
+                play_member_sound(
+                    &ctx,
+                    &settings,
+                    guild_id,
+                    &voice_state,
+                    channel_id,
+                    SoundKind::Join,
+                    first_join,
+                )
+                .await
+            }
+        }
+    });
 }
 
-#[async_trait]
-impl SongbirdEventHandler for SongEndNotifier {
-    #[instrument(name = "songbird-end-notifier", skip(_ctx))]
-    async fn act(&self, _ctx: &SongbirdEventContext<'_>) -> Option<songbird::events::Event> {
-        info!("leaving now");
-        let mut handler = (*self.call).lock().await;
-        if let Err(why) = handler.leave().await {
-            error!("Error leaving voice channel: {:?}", why);
+async fn play_group_sound(
+    ctx: &serenity::client::Context,
+    settings: &GuildSettings,
+    guild_id: GuildId,
+    voice_state: &VoiceState,
+    channel_id: ChannelId,
+    path: String,
+) {
+    let record = |sound: Option<&str>, outcome| {
+        backend::history::record_play(
+            guild_id,
+            channel_id,
+            voice_state.user_id,
+            PlaySource::Group,
+            sound,
+            outcome,
+        )
+    };
+    if let Some(reason) = skip_reason(ctx, settings, guild_id, voice_state, channel_id) {
+        info!("Skipping sound: {}", reason);
+        record(None, PlayOutcome::Skipped(reason.to_string()));
+        return;
+    }
+    let path = match backend::file::canonicalize_file_path(PathBuf::from(path)).await {
+        Ok(path) => path,
+        Err(why) => {
+            error!("Could not get group sound file: {}", why);
+            record(None, PlayOutcome::Failed(why.to_string()));
+            return;
         }
+    };
+    let sound = backend::history::sound_name(&path);
+    let queued = QueuedSound::recorded(None, voice_state.user_id, PlaySource::Group, sound.clone());
+    match playback::play_sound(ctx, guild_id, channel_id, path, queued).await {
+        Ok(_) => backend::cooldowns::set_last_triggered(guild_id, voice_state.user_id),
+        Err(why) => {
+            error!("Error playing group sound: {}", why);
+            record(Some(&sound), PlayOutcome::Failed(why.to_string()));
+        }
+    }
+}
 
+/// Find the sound kind for a change in a member's voice state within the same
+/// channel, if the guild plays sounds for it.
+fn voice_state_trigger(
+    settings: &GuildSettings,
+    old: &VoiceState,
+    new: &VoiceState,
+) -> Option<SoundKind> {
+    let started_stream = !old.self_stream.unwrap_or(false) && new.self_stream.unwrap_or(false);
+    let started_video = !old.self_video && new.self_video;
+    // Undeafening also unmutes, but that isn't the member choosing to talk
+    let unmuted = old.self_mute && !new.self_mute && !old.self_deaf && !old.deaf;
+    if !started_stream && !started_video && !unmuted {
         return None;
     }
+    if started_stream && settings.play_on_stream {
+        Some(SoundKind::Stream)
+    } else if started_video && settings.play_on_camera {
+        Some(SoundKind::Camera)
+    } else if unmuted && settings.play_on_unmute {
+        Some(SoundKind::Unmute)
+    } else {
+        None
+    }
+}
+
+/// Check the guild's rules for when sounds should not play at all.
+fn skip_reason(
+    ctx: &serenity::client::Context,
+    settings: &GuildSettings,
+    guild_id: GuildId,
+    voice_state: &VoiceState,
+    channel_id: ChannelId,
+) -> Option<&'static str> {
+    if let Some(reason) = channel_skip_reason(ctx, settings, guild_id, channel_id) {
+        return Some(reason);
+    }
+    if settings.ignore_deafened && (voice_state.self_deaf || voice_state.deaf) {
+        return Some("member is deafened");
+    }
+    if settings.ignore_muted && voice_state.mute {
+        return Some("member is server muted");
+    }
+    let other_members =
+        playback::other_humans_in_channel(ctx, guild_id, channel_id, voice_state.user_id);
+    if other_members < settings.min_other_members.max(0) as usize {
+        return Some("not enough members in the channel");
+    }
+    None
+}
+
+/// Check the guild's rules for when no sounds should play in a channel,
+/// whoever they are for.
+pub fn channel_skip_reason(
+    ctx: &serenity::client::Context,
+    settings: &GuildSettings,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Option<&'static str> {
+    if settings.paused_until().is_some() {
+        return Some("sounds are paused");
+    }
+    if backend::guild_settings::is_quiet_hours(guild_id, settings) {
+        return Some("quiet hours");
+    }
+    let category = ctx.cache.guild(guild_id).and_then(|guild| {
+        guild
+            .channels
+            .get(&channel_id)
+            .and_then(|channel| channel.parent_id)
+    });
+    if !backend::guild_settings::is_channel_allowed(guild_id, channel_id, category) {
+        return Some("channel is not allowed");
+    }
+    if settings.ignore_afk_channel {
+        let afk_channel = ctx
+            .cache
+            .guild(guild_id)
+            .and_then(|guild| guild.afk_metadata.as_ref().map(|afk| afk.afk_channel_id));
+        if afk_channel == Some(channel_id) {
+            return Some("AFK channel");
+        }
+    }
+    None
+}
+
+/// Find the theme that should play for a member joining `channel_id`.
+fn channel_theme(
+    ctx: &serenity::client::Context,
+    guild_id: GuildId,
+    voice_state: &VoiceState,
+    channel_id: ChannelId,
+) -> Option<backend::models::ChannelTheme> {
+    let theme = backend::themes::get_theme(guild_id, channel_id)?;
+    if theme.first_join_only
+        && playback::other_humans_in_channel(ctx, guild_id, channel_id, voice_state.user_id) > 0
+    {
+        return None;
+    }
+    Some(theme)
+}
+
+/// Play a member's sound of `kind`, or the theme or default sound that stands
+/// in for it. `first_join` is whether this is their first join in the guild.
+async fn play_member_sound(
+    ctx: &serenity::client::Context,
+    settings: &GuildSettings,
+    guild_id: GuildId,
+    voice_state: &VoiceState,
+    channel_id: ChannelId,
+    kind: SoundKind,
+    first_join: bool,
+) {
+    let user_id = voice_state.user_id;
+    let has_own_sound = backend::has_sound(user_id, Some(guild_id), Some(channel_id), kind)
+        || backend::has_sound(user_id, None, None, kind);
+    let (theme, default_sound) = match kind {
+        SoundKind::Join => (
+            channel_theme(ctx, guild_id, voice_state, channel_id),
+            backend::default_sound::get_default_sound(guild_id)
+                .filter(|sound| !has_own_sound && (first_join || !sound.first_join_only)),
+        ),
+        _ => (None, None),
+    };
+    let has_member_sound = has_own_sound || default_sound.is_some();
+    let source = if has_own_sound {
+        PlaySource::Member(kind)
+    } else if default_sound.is_some() {
+        PlaySource::Default
+    } else if theme.is_some() {
+        PlaySource::Theme
+    } else {
+        return;
+    };
+    let record = |source, sound: Option<&str>, outcome| {
+        backend::history::record_play(guild_id, channel_id, user_id, source, sound, outcome)
+    };
+
+    if let Some(reason) = skip_reason(ctx, settings, guild_id, voice_state, channel_id) {
+        info!("Skipping sound: {}", reason);
+        record(source, None, PlayOutcome::Skipped(reason.to_string()));
+        return;
+    }
+
+    // Only joins are rate limited, so a member's other sounds don't eat into
+    // their join cooldown or get blocked by it.
+    let cooldown = if kind == SoundKind::Join {
+        backend::cooldowns::remaining_cooldown(guild_id, user_id, settings)
+    } else {
+        None
+    };
+    if let Some(remaining) = cooldown {
+        warn!(
+            "Too soon to play sound, {}s left on cooldown.",
+            remaining.num_seconds()
+        );
+        record(source, None, PlayOutcome::Skipped("cooldown".to_string()));
+        return;
+    }
+
+    let (play_theme, play_user_sound) = match theme {
+        None => (false, true),
+        Some(_) if !has_member_sound => (true, false),
+        Some(_) => match settings.theme_policy() {
+            ThemePolicy::Theme => (true, false),
+            ThemePolicy::User => (false, true),
+            ThemePolicy::Both => (true, true),
+        },
+    };
+
+    let mut sounds = Vec::new();
+    if let Some(theme) = theme.filter(|_| play_theme) {
+        match backend::themes::get_theme_file(&theme).await {
+            Ok(path) => sounds.push((path, None, PlaySource::Theme)),
+            Err(why) => {
+                error!("Could not get channel theme file: {}", why);
+                record(
+                    PlaySource::Theme,
+                    None,
+                    PlayOutcome::Failed(why.to_string()),
+                );
+            }
+        }
+    }
+    if play_user_sound {
+        if let Some(default_sound) = default_sound {
+            match backend::default_sound::get_default_sound_file(&default_sound).await {
+                Ok(path) => sounds.push((path, Some(user_id), PlaySource::Default)),
+                Err(why) => {
+                    error!("Could not get default sound file: {}", why);
+                    record(
+                        PlaySource::Default,
+                        None,
+                        PlayOutcome::Failed(why.to_string()),
+                    );
+                }
+            }
+        } else {
+            match backend::get_sound(user_id, guild_id, channel_id, kind).await {
+                Ok(joinsound) => sounds.push((joinsound, Some(user_id), PlaySource::Member(kind))),
+                Err(why) => {
+                    error!("no joinsound");
+                    record(PlaySource::Member(kind), None, PlayOutcome::Failed(why));
+                }
+            }
+        }
+    }
+
+    let mut played = false;
+    for (path, owner, source) in sounds {
+        let sound = backend::history::sound_name(&path);
+        let queued = QueuedSound::recorded(owner, user_id, source, sound.clone());
+        match playback::play_sound(ctx, guild_id, channel_id, path, queued).await {
+            Ok(_) => played = true,
+            Err(why) => {
+                error!("Error playing joinsound: {}", why);
+                record(source, Some(&sound), PlayOutcome::Failed(why.to_string()));
+            }
+        }
+    }
+    if played && kind == SoundKind::Join {
+        backend::cooldowns::set_last_triggered(guild_id, user_id);
+    }
 }
@@ -2,11 +2,21 @@ use std::collections::HashMap;
 use std::env;
 use std::path::Path;
 
+use backend::guild_settings::{BusyPolicy, ChannelList, DropPolicy, MovePolicy, ThemePolicy};
+use backend::history::{PlayOutcome, PlaySource};
+use backend::models::{PlayEvent, SoundboardSound};
+use backend::rotation::RotationMode;
+use backend::{Schedule, SoundKind};
+use chrono::{NaiveDate, NaiveTime, Weekday};
+use chrono_tz::Tz;
 use jsj_backend as backend;
-use poise::serenity_prelude::Attachment;
+use playback::QueuedSound;
+use poise::serenity_prelude::{Attachment, ChannelId, GuildChannel, GuildId, Member, Role, User};
+use poise::ChoiceParameter;
 use serenity::all::{
     colours, ActivityData, ButtonStyle, ComponentInteractionCollector, CreateActionRow,
-    CreateButton, CreateInteractionResponse, ReactionType,
+    CreateAllowedMentions, CreateButton, CreateInteractionResponse,
+    CreateInteractionResponseMessage, ReactionType,
 };
 use serenity::model::gateway::GatewayIntents;
 use serenity::model::user::OnlineStatus;
@@ -28,6 +38,7 @@ type Error = Box<dyn std::error::Error + Send + Sync>;
 type Context<'a> = poise::Context<'a, Data, Error>;
 
 mod event_listener;
+mod playback;
 
 #[derive(Parser)]
 struct Cli {
@@ -50,6 +61,16 @@ enum SubCommands {
     MigrateMediaToFileSystem,
 }
 
+fn sound_name(kind: SoundKind) -> &'static str {
+    match kind {
+        SoundKind::Join => "joinsound",
+        SoundKind::Leave => "leave sound",
+        SoundKind::Stream => "stream sound",
+        SoundKind::Camera => "camera sound",
+        SoundKind::Unmute => "unmute sound",
+    }
+}
+
 fn changing_sounds_disabled() -> bool {
     if let Ok(disabled) = env::var("DISABLE_JOINSOUND_UPDATES") {
         !disabled.is_empty()
@@ -104,6 +125,136 @@ Set a sound to play everytime you join a voice channel!",
     Ok(())
 }
 
+/// Show a pending sound with buttons to preview, confirm or cancel it.
+/// Returns whether it was confirmed. `previewed` is set once a preview has
+/// been queued, since the pending file must outlive it.
+async fn review_sound(
+    ctx: Context<'_>,
+    message: &poise::ReplyHandle<'_>,
+    sound: &backend::attachments::PendingSound,
+    kind: SoundKind,
+    previewed: &mut bool,
+) -> Result<bool, Error> {
+    let interaction_uuid = ctx.id();
+    let mut buttons = vec![];
+    if ctx.guild_id().is_some() {
+        buttons.push(
+            CreateButton::new(format!("{interaction_uuid}-play"))
+                .style(ButtonStyle::Secondary)
+                .emoji(ReactionType::from('🔊'))
+                .label("Play in my channel"),
+        );
+    }
+    buttons.push(
+        CreateButton::new(format!("{interaction_uuid}-confirm"))
+            .style(ButtonStyle::Success)
+            .label("Confirm"),
+    );
+    buttons.push(
+        CreateButton::new(format!("{interaction_uuid}-cancel"))
+            .style(ButtonStyle::Danger)
+            .label("Cancel"),
+    );
+    let preview = poise::serenity_prelude::CreateAttachment::file(
+        &tokio::fs::File::open(&sound.temp_path).await?,
+        sound.filename.clone(),
+    )
+    .await?;
+    message
+        .edit(
+            ctx,
+            poise::CreateReply::default()
+                .content(format!(
+                    "🔊 Here is your new {}. Add it to your sounds?",
+                    sound_name(kind)
+                ))
+                .attachment(preview)
+                .components(vec![CreateActionRow::Buttons(buttons)]),
+        )
+        .await?;
+
+    while let Some(mci) = ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .channel_id(ctx.channel_id())
+        .timeout(std::time::Duration::from_secs(120))
+        .filter(move |mci| {
+            mci.data
+                .custom_id
+                .starts_with(&format!("{interaction_uuid}-"))
+        })
+        .await
+    {
+        mci.create_response(ctx, CreateInteractionResponse::Acknowledge)
+            .await?;
+        match mci
+            .data
+            .custom_id
+            .trim_start_matches(&format!("{interaction_uuid}-"))
+        {
+            "play" => {
+                let (Some(guild_id), Some(member)) = (ctx.guild_id(), ctx.author_member().await)
+                else {
+                    continue;
+                };
+                match playable_voice_channel(ctx.serenity_context(), guild_id, &member) {
+                    Ok(channel_id) => {
+                        match playback::play_sound(
+                            ctx.serenity_context(),
+                            guild_id,
+                            channel_id,
+                            sound.temp_path.clone(),
+                            QueuedSound {
+                                owner: Some(ctx.author().id),
+                                play: None,
+                            },
+                        )
+                        .await
+                        {
+                            Ok(_) => *previewed = true,
+                            Err(why) => error!("Error playing preview: {}", why),
+                        }
+                    }
+                    Err(why) => {
+                        ctx.send(
+                            poise::CreateReply::default()
+                                .content(format!("❌ {why}"))
+                                .ephemeral(true),
+                        )
+                        .await?;
+                    }
+                }
+            }
+            "confirm" => return Ok(true),
+            _ => return Ok(false),
+        }
+    }
+    Ok(false)
+}
+
+/// Remove a pending sound's file once it is no longer needed. A queued
+/// preview still reads it, so then wait for the bot to finish playing first.
+async fn discard_after_previews(
+    ctx: Context<'_>,
+    sound: backend::attachments::PendingSound,
+    previewed: bool,
+) {
+    let Some(guild_id) = ctx.guild_id().filter(|_| previewed) else {
+        backend::attachments::discard_sound(&sound).await;
+        return;
+    };
+    let ctx = ctx.serenity_context().clone();
+    tokio::spawn(async move {
+        // Give up after ten minutes, in case the bot never goes quiet
+        for _ in 0..120 {
+            if playback::is_idle(&ctx, guild_id).await {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        }
+        backend::attachments::discard_sound(&sound).await;
+    });
+}
+
 #[instrument(
     name="set_sound",
     skip(ctx, attachment),
@@ -113,8 +264,17 @@ Set a sound to play everytime you join a voice channel!",
         file_name=%attachment.filename,
     )
 )]
-async fn set_sound(ctx: Context<'_>, attachment: Attachment, local: bool) -> Result<(), Error> {
+async fn set_sound(
+    ctx: Context<'_>,
+    attachment: Attachment,
+    local: bool,
+    channel: Option<ChannelId>,
+    kind: SoundKind,
+    schedule: Schedule,
+) -> Result<(), Error> {
     info!("Trying to set sound");
+    // Channel sounds are always local to their server
+    let local = local || channel.is_some();
     if changing_sounds_disabled() {
         ctx.say("❌ Setting Joinsounds is temporarily disabled. Please try again shortly.")
             .await?;
@@ -139,48 +299,56 @@ async fn set_sound(ctx: Context<'_>, attachment: Attachment, local: bool) -> Res
                 None => None,
             };
 
-            if backend::has_sound(ctx.author().id, guild_id) {
-                if let Err(why) = match backend::update_sound(ctx.author().id, attachment, guild_id)
-                    .await
-                {
-                    Ok(_) => {
-                        message
-                            .edit(
-                                ctx,
-                                poise::CreateReply::default().content("✅ Successful!".to_string()),
-                            )
-                            .await
-                    }
-                    Err(why) => {
-                        message
-                            .edit(
-                                ctx,
-                                poise::CreateReply::default().content(format!("❌ Error: {why}")),
-                            )
-                            .await
+            let sound = match backend::prepare_sound(attachment).await {
+                Ok(sound) => sound,
+                Err(why) => {
+                    if let Err(why) = message
+                        .edit(
+                            ctx,
+                            poise::CreateReply::default().content(format!("❌ Error: {why}")),
+                        )
+                        .await
+                    {
+                        error!("Error sending message: {}", why);
                     }
-                } {
-                    error!("Error sending message: {}", why);
+                    return Ok(());
                 }
-            } else if let Err(why) =
-                match backend::upload_sound(ctx.author().id, attachment, guild_id).await {
-                    Ok(_) => {
-                        message
-                            .edit(
-                                ctx,
-                                poise::CreateReply::default().content("✅ Successful!".to_string()),
-                            )
-                            .await
-                    }
-                    Err(why) => {
-                        message
-                            .edit(
-                                ctx,
-                                poise::CreateReply::default().content(format!("❌ Error: {why}")),
-                            )
-                            .await
-                    }
+            };
+
+            let mut previewed = false;
+            let result = match review_sound(ctx, &message, &sound, kind, &mut previewed).await {
+                Ok(true) => Some(
+                    backend::confirm_sound(
+                        ctx.author().id,
+                        &sound,
+                        guild_id,
+                        channel,
+                        kind,
+                        schedule,
+                    )
+                    .await,
+                ),
+                Ok(false) => None,
+                Err(why) => {
+                    discard_after_previews(ctx, sound, previewed).await;
+                    return Err(why);
                 }
+            };
+            discard_after_previews(ctx, sound, previewed).await;
+
+            let content = match result {
+                Some(Ok(_)) => "✅ Successful!".to_string(),
+                Some(Err(why)) => format!("❌ Error: {why}"),
+                None => format!("❌ Cancelled, your {} was not changed.", sound_name(kind)),
+            };
+            if let Err(why) = message
+                .edit(
+                    ctx,
+                    poise::CreateReply::default()
+                        .content(content)
+                        .components(vec![]),
+                )
+                .await
             {
                 error!("Error sending message: {}", why);
             }
@@ -191,32 +359,154 @@ async fn set_sound(ctx: Context<'_>, attachment: Attachment, local: bool) -> Res
     Ok(())
 }
 
-/// Set a join sound.
+/// Parse a list of days like `mon,fri`, `weekdays` or `weekends` into a bitmask
+/// with Monday as the lowest bit.
+fn parse_weekdays(weekdays: &str) -> Result<i32, String> {
+    let mut mask = 0;
+    for day in weekdays.split(',').map(|day| day.trim().to_lowercase()) {
+        mask |= match day.as_str() {
+            "weekdays" => 0b0011111,
+            "weekends" => 0b1100000,
+            day => {
+                let weekday = day
+                    .parse::<Weekday>()
+                    .map_err(|_| format!("`{day}` is not a day of the week."))?;
+                1 << weekday.num_days_from_monday()
+            }
+        };
+    }
+    Ok(mask)
+}
+
+fn format_weekdays(mask: i32) -> String {
+    std::iter::successors(Some(Weekday::Mon), |day| Some(day.succ()))
+        .take(7)
+        .filter(|day| mask & (1 << day.num_days_from_monday()) != 0)
+        .map(|day| day.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parse when a sound should play. Dates are whole days in UTC and the end
+/// date is included. Times of day and weekdays are in the user's timezone.
+fn parse_schedule(
+    from: Option<String>,
+    until: Option<String>,
+    days: Option<u32>,
+    after: Option<String>,
+    before: Option<String>,
+    weekdays: Option<String>,
+) -> Result<Schedule, String> {
+    let parse_time = |time: &str| {
+        NaiveTime::parse_from_str(time.trim(), "%H:%M")
+            .map_err(|_| format!("`{time}` is not a valid time, use HH:MM."))
+    };
+    let parse_date = |date: &str| {
+        NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+            .map(|date| date.and_time(NaiveTime::MIN))
+            .map_err(|_| format!("`{date}` is not a valid date, use YYYY-MM-DD."))
+    };
+    let starts_at = from.as_deref().map(parse_date).transpose()?;
+    let ends_at = match (until, days) {
+        (Some(_), Some(_)) => return Err("Use either until or days, not both.".to_string()),
+        (Some(until), None) => Some(parse_date(&until)? + chrono::Duration::days(1)),
+        (None, Some(days)) => Some(
+            starts_at.unwrap_or_else(|| chrono::Utc::now().naive_utc())
+                + chrono::Duration::days(days.into()),
+        ),
+        (None, None) => None,
+    };
+    if let Some(ends_at) = ends_at {
+        if ends_at <= chrono::Utc::now().naive_utc() {
+            return Err("The sound would already be over.".to_string());
+        }
+        if starts_at.is_some_and(|starts_at| ends_at <= starts_at) {
+            return Err("The sound must end after it starts.".to_string());
+        }
+    }
+    let time_start = after.as_deref().map(parse_time).transpose()?;
+    let time_end = before.as_deref().map(parse_time).transpose()?;
+    if time_start.is_some() && time_start == time_end {
+        return Err("The sound must play after and before different times.".to_string());
+    }
+    Ok(Schedule {
+        starts_at,
+        ends_at,
+        time_start,
+        time_end,
+        weekdays: weekdays.as_deref().map(parse_weekdays).transpose()?,
+    })
+}
+
+/// Add a join sound.
 #[poise::command(prefix_command, slash_command, track_edits)]
+#[allow(clippy::too_many_arguments)]
 async fn set(
     ctx: Context<'_>,
     #[description = "Joinsound."] attachment: Attachment,
     #[description = "If true, this joinsound will only play in this server."]
     #[flag]
     local: bool,
+    #[description = "When the sound plays. Defaults to join."] kind: Option<SoundKind>,
+    #[description = "Only play the sound from this date on, like 2024-12-24."] from: Option<String>,
+    #[description = "Only play the sound until this date, like 2024-12-26."] until: Option<String>,
+    #[description = "Only play the sound for this many days."]
+    #[min = 1]
+    #[max = 365]
+    days: Option<u32>,
+    #[description = "Only play the sound after this time of day, like 06:00."] after: Option<
+        String,
+    >,
+    #[description = "Only play the sound before this time of day, like 12:00."] before: Option<
+        String,
+    >,
+    #[description = "Only play the sound on these days, like mon,fri or weekends."]
+    weekdays: Option<String>,
+    #[description = "Only play the sound when joining this voice channel."]
+    #[channel_types("Voice")]
+    channel: Option<GuildChannel>,
 ) -> Result<(), Error> {
     ctx.defer_ephemeral().await?;
-    set_sound(ctx, attachment, local).await?;
+    let schedule = match parse_schedule(from, until, days, after, before, weekdays) {
+        Ok(schedule) => schedule,
+        Err(why) => {
+            ctx.say(format!("❌ Error: {why}")).await?;
+            return Ok(());
+        }
+    };
+    set_sound(
+        ctx,
+        attachment,
+        local,
+        channel.map(|channel| channel.id),
+        kind.unwrap_or(SoundKind::Join),
+        schedule,
+    )
+    .await?;
     Ok(())
 }
 
-/// Set a sound that is local to this discord server.
+/// Add a sound that is local to this discord server.
 #[poise::command(prefix_command, slash_command, track_edits)]
 async fn set_local(
     ctx: Context<'_>,
     #[description = "Joinsound."] attachment: Attachment,
+    #[description = "When the sound plays. Defaults to join."] kind: Option<SoundKind>,
 ) -> Result<(), Error> {
     ctx.defer_ephemeral().await?;
-    set_sound(ctx, attachment, true).await?;
+    set_sound(
+        ctx,
+        attachment,
+        true,
+        None,
+        kind.unwrap_or(SoundKind::Join),
+        Schedule::default(),
+    )
+    .await?;
     Ok(())
 }
 
-/// View what your joinsound currently is.
+/// View what your joinsounds currently are.
 #[poise::command(prefix_command, slash_command, track_edits)]
 #[instrument(
     name="view",
@@ -230,8 +520,15 @@ async fn view(
     #[description = "If true, the joinsound local to this server will be shown."]
     #[flag]
     local: bool,
+    #[description = "Which sound to show. Defaults to join."] kind: Option<SoundKind>,
+    #[description = "Show the sounds for this voice channel."]
+    #[channel_types("Voice")]
+    channel: Option<GuildChannel>,
 ) -> Result<(), Error> {
     info!("Viewing joinsound");
+    let kind = kind.unwrap_or(SoundKind::Join);
+    let channel_id = channel.map(|channel| channel.id);
+    let local = local || channel_id.is_some();
     ctx.defer_ephemeral().await?;
     if ctx.guild().is_none() && local {
         ctx.say("❌ Must be in a server to view local joinsound")
@@ -251,31 +548,74 @@ async fn view(
                 None => None,
             };
 
-            if let Err(why) = match backend::get_sound_path(ctx.author().id, guild_id).await {
-                Ok(path) => {
-                    let file_path = Path::new(&path);
-                    let attachment_type =
-                        poise::serenity_prelude::CreateAttachment::path(file_path)
+            if let Err(why) =
+                match backend::get_sound_paths(ctx.author().id, guild_id, channel_id, kind).await {
+                    Ok(sounds) => {
+                        let mode = backend::rotation::get_rotation_mode(
+                            ctx.author().id,
+                            guild_id,
+                            channel_id,
+                            kind,
+                        );
+                        let mut reply = poise::CreateReply::default();
+                        let mut lines = vec![];
+                        for (number, (sound, path)) in sounds.iter().enumerate() {
+                            let attachment_type =
+                                poise::serenity_prelude::CreateAttachment::path(Path::new(path))
+                                    .await
+                                    .expect("Failure when creating attachment.");
+                            let mut line = format!(
+                                "{}. {} (weight {})",
+                                number + 1,
+                                attachment_type.filename,
+                                sound.weight
+                            );
+                            if let Some(starts_at) = sound.starts_at {
+                                line +=
+                                    &format!(", from <t:{}:d>", starts_at.and_utc().timestamp());
+                            }
+                            if let Some(ends_at) = sound.ends_at {
+                                line += &format!(", until <t:{}:f>", ends_at.and_utc().timestamp());
+                            }
+                            if sound.time_start.is_some() || sound.time_end.is_some() {
+                                let format_time = |time: Option<NaiveTime>| {
+                                    time.map(|time| time.format("%H:%M").to_string())
+                                        .unwrap_or_default()
+                                };
+                                line += &format!(
+                                    ", {}–{}",
+                                    format_time(sound.time_start),
+                                    format_time(sound.time_end)
+                                );
+                            }
+                            if let Some(weekdays) = sound.weekdays {
+                                line += &format!(", on {}", format_weekdays(weekdays));
+                            }
+                            lines.push(line);
+                            reply = reply.attachment(attachment_type);
+                        }
+                        message
+                            .edit(
+                                ctx,
+                                reply.content(format!(
+                                    "✅ Your {}s, played in {} order:\n{}",
+                                    sound_name(kind),
+                                    mode.name(),
+                                    lines.join("\n")
+                                )),
+                            )
                             .await
-                            .expect("Failure when creating attachment.");
-                    message
-                        .edit(
-                            ctx,
-                            poise::CreateReply::default()
-                                .content("✅ Your joinsound is:".to_string())
-                                .attachment(attachment_type),
-                        )
-                        .await
-                }
-                Err(why) => {
-                    message
-                        .edit(
-                            ctx,
-                            poise::CreateReply::default().content(format!("❌ Error: {why}")),
-                        )
-                        .await
+                    }
+                    Err(why) => {
+                        message
+                            .edit(
+                                ctx,
+                                poise::CreateReply::default().content(format!("❌ Error: {why}")),
+                            )
+                            .await
+                    }
                 }
-            } {
+            {
                 error!("Error sending message: {}", why);
             }
         }
@@ -284,8 +624,15 @@ async fn view(
     Ok(())
 }
 
-async fn _remove(ctx: Context<'_>, local: bool) -> Result<(), Error> {
+async fn _remove(
+    ctx: Context<'_>,
+    local: bool,
+    channel_id: Option<ChannelId>,
+    kind: SoundKind,
+    number: Option<u32>,
+) -> Result<(), Error> {
     info!("Removing joinsound");
+    let local = local || channel_id.is_some();
     ctx.defer_ephemeral().await?;
     if changing_sounds_disabled() {
         ctx.say("❌ Removing Joinsounds is temporarily disabled. Please try again shortly.")
@@ -310,16 +657,35 @@ async fn _remove(ctx: Context<'_>, local: bool) -> Result<(), Error> {
                 None => None,
             };
 
-            if let Err(why) = match backend::remove_sound(ctx.author().id, guild_id).await {
+            let result = match number {
+                Some(number) => {
+                    backend::remove_sound_entry(
+                        ctx.author().id,
+                        guild_id,
+                        channel_id,
+                        kind,
+                        number as usize,
+                    )
+                    .await
+                }
+                None => backend::remove_sound(ctx.author().id, guild_id, channel_id, kind).await,
+            };
+            if let Err(why) = match result {
                 Ok(_) => {
-                    let remove_context = if local { "local" } else { "global" };
+                    let remove_context = match channel_id {
+                        Some(channel_id) => format!("<#{channel_id}>"),
+                        None if local => "local".to_string(),
+                        None => "global".to_string(),
+                    };
+                    let removed = match number {
+                        Some(number) => format!("{remove_context} {} #{number}", sound_name(kind)),
+                        None => format!("{remove_context} {}s", sound_name(kind)),
+                    };
                     message
                         .edit(
                             ctx,
-                            poise::CreateReply::default().content(
-                                format!("✅ Successfully removed {remove_context} joinsound!")
-                                    .to_string(),
-                            ),
+                            poise::CreateReply::default()
+                                .content(format!("✅ Successfully removed {removed}!")),
                         )
                         .await
                 }
@@ -355,8 +721,22 @@ async fn remove(
     #[description = "If true, the joinsound local to this server will be removed."]
     #[flag]
     local: bool,
+    #[description = "Which sound to remove. Defaults to join."] kind: Option<SoundKind>,
+    #[description = "Number of the sound to remove, as shown by /view. Leave empty to remove all."]
+    #[min = 1]
+    number: Option<u32>,
+    #[description = "Remove the sounds for this voice channel."]
+    #[channel_types("Voice")]
+    channel: Option<GuildChannel>,
 ) -> Result<(), Error> {
-    _remove(ctx, local).await?;
+    _remove(
+        ctx,
+        local,
+        channel.map(|channel| channel.id),
+        kind.unwrap_or(SoundKind::Join),
+        number,
+    )
+    .await?;
     Ok(())
 }
 
@@ -369,96 +749,1186 @@ async fn remove(
         user_id=%ctx.author(),
     )
 )]
-async fn remove_local(ctx: Context<'_>) -> Result<(), Error> {
-    _remove(ctx, true).await?;
+async fn remove_local(
+    ctx: Context<'_>,
+    #[description = "Which sound to remove. Defaults to join."] kind: Option<SoundKind>,
+    #[description = "Number of the sound to remove, as shown by /view. Leave empty to remove all."]
+    #[min = 1]
+    number: Option<u32>,
+) -> Result<(), Error> {
+    _remove(ctx, true, None, kind.unwrap_or(SoundKind::Join), number).await?;
     Ok(())
 }
 
-/// Removes all user data and join sounds from the bot.
-#[poise::command(slash_command)]
+/// Choose how your sounds take turns when you have more than one.
+#[poise::command(prefix_command, slash_command, track_edits)]
 #[instrument(
-    name="purge",
+    name="rotation",
     skip(ctx),
     fields(
         user_id=%ctx.author(),
     )
 )]
-async fn purge(ctx: Context<'_>) -> Result<(), Error> {
-    if changing_sounds_disabled() {
-        ctx.say("❌ Purging Joinsounds is temporarily disabled. Please try again shortly.")
-            .await?;
-        return Ok(());
-    }
-    let interaction_uuid = ctx.id();
-    let components = vec![CreateActionRow::Buttons(vec![CreateButton::new(format!(
-        "{interaction_uuid}"
+async fn rotation(
+    ctx: Context<'_>,
+    #[description = "How the next sound is picked."] mode: RotationMode,
+    #[description = "If true, change the rotation of the sounds local to this server."]
+    #[flag]
+    local: bool,
+    #[description = "Which sounds to change. Defaults to join."] kind: Option<SoundKind>,
+    #[description = "Change the sounds for this voice channel."]
+    #[channel_types("Voice")]
+    channel: Option<GuildChannel>,
+) -> Result<(), Error> {
+    info!("Setting rotation mode");
+    let kind = kind.unwrap_or(SoundKind::Join);
+    let channel_id = channel.map(|channel| channel.id);
+    let local = local || channel_id.is_some();
+    ctx.defer_ephemeral().await?;
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) if local => Some(guild_id),
+        None if local => {
+            ctx.say("❌ Must be in a server to change local sounds")
+                .await?;
+            return Ok(());
+        }
+        _ => None,
+    };
+    backend::rotation::set_rotation_mode(ctx.author().id, guild_id, channel_id, kind, mode);
+    ctx.say(format!(
+        "✅ Your {}s will play in {} order.",
+        sound_name(kind),
+        mode.name()
     ))
-    .style(ButtonStyle::Danger)
-    .emoji(ReactionType::from('🗑'))
-    .label("Delete all data")])];
-
-    ctx.send(poise::CreateReply::default()
-        .embed(poise::serenity_prelude::CreateEmbed::new()
-            .title("Purge Data")
-            .description("Are you sure you'd like to purge or delete all data? This is not reversible.")
-            .colour(colours::css::DANGER)
-        )
-        .components(components)
-        .ephemeral(true)
-    ).await?;
+    .await?;
+    Ok(())
+}
 
-    while let Some(mci) = ComponentInteractionCollector::new(ctx)
-        .author_id(ctx.author().id)
-        .channel_id(ctx.channel_id())
-        .timeout(std::time::Duration::from_secs(120))
-        .filter(move |mci| mci.data.custom_id == interaction_uuid.to_string())
-        .await
-    {
-        ctx.defer().await?;
-        if let Err(why) = backend::remove_all_sounds(ctx.author().id).await {
-            ctx.send(
-                poise::CreateReply::default()
-                    .content(format!("Error when deleting data: {why}."))
-                    .ephemeral(true),
-            )
-            .await?;
-        } else {
-            ctx.send(
-                poise::CreateReply::default()
-                    .content("Data has been deleted.".to_string())
-                    .ephemeral(true),
-            )
-            .await?;
+/// Make one of your sounds more or less likely to play in weighted random order.
+#[poise::command(prefix_command, slash_command, track_edits)]
+#[instrument(
+    name="weight",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn weight(
+    ctx: Context<'_>,
+    #[description = "Number of the sound, as shown by /view."]
+    #[min = 1]
+    number: u32,
+    #[description = "Relative chance of playing. 0 never plays it."]
+    #[max = 100]
+    weight: u32,
+    #[description = "If true, change a sound local to this server."]
+    #[flag]
+    local: bool,
+    #[description = "Which sounds to change. Defaults to join."] kind: Option<SoundKind>,
+    #[description = "Change the sounds for this voice channel."]
+    #[channel_types("Voice")]
+    channel: Option<GuildChannel>,
+) -> Result<(), Error> {
+    info!("Setting sound weight");
+    let kind = kind.unwrap_or(SoundKind::Join);
+    let channel_id = channel.map(|channel| channel.id);
+    let local = local || channel_id.is_some();
+    ctx.defer_ephemeral().await?;
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) if local => Some(guild_id),
+        None if local => {
+            ctx.say("❌ Must be in a server to change local sounds")
+                .await?;
+            return Ok(());
         }
+        _ => None,
+    };
+    let content = match backend::set_sound_weight(
+        ctx.author().id,
+        guild_id,
+        channel_id,
+        kind,
+        number as usize,
+        weight as i32,
+    ) {
+        Ok(_) => format!("✅ Sound #{number} now has weight {weight}."),
+        Err(why) => format!("❌ Error: {why}"),
+    };
+    ctx.say(content).await?;
+    Ok(())
+}
 
-        mci.create_response(ctx, CreateInteractionResponse::Acknowledge)
-            .await?;
-    }
+async fn autocomplete_library_sound<'a>(
+    ctx: Context<'a>,
+    partial: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+    let partial = partial.to_lowercase();
+    backend::library::list_library_sounds(ctx.author().id)
+        .into_iter()
+        .map(|sound| sound.name)
+        .filter(move |name| name.to_lowercase().contains(&partial))
+        .take(25)
+}
 
+/// Keep named sounds that you can use as your joinsound anywhere.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("library_add", "library_list", "library_assign", "library_remove"),
+    subcommand_required
+)]
+async fn library(_ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
-/// Force the bot to leave a voice channel.
-#[poise::command(prefix_command, slash_command, track_edits)]
+/// Upload a sound to your library.
+#[poise::command(prefix_command, slash_command, rename = "add")]
 #[instrument(
-    name="leave",
-    skip(ctx),
+    name="library_add",
+    skip(ctx, attachment),
     fields(
         user_id=%ctx.author(),
+        attachment_id=%attachment.id,
     )
 )]
-async fn leave(ctx: Context<'_>) -> Result<(), Error> {
-    info!("Leaving voice channel");
+async fn library_add(
+    ctx: Context<'_>,
+    #[description = "Name to use for the sound."]
+    #[max_length = 100]
+    name: String,
+    #[description = "Sound to upload."] attachment: Attachment,
+) -> Result<(), Error> {
+    info!("Adding library sound");
     ctx.defer_ephemeral().await?;
-    match ctx.say("🔃 Leaving...").await {
-        Ok(message) => {
-            if let Some(guild_id) = ctx.guild_id() {
-                let manager = songbird::get(ctx.serenity_context())
-                    .await
-                    .expect("Songbird Voice client placed in at initialisation.")
-                    .clone();
-                let has_handler = manager.get(guild_id).is_some();
-                if has_handler {
+    if changing_sounds_disabled() {
+        ctx.say("❌ Setting Joinsounds is temporarily disabled. Please try again shortly.")
+            .await?;
+        return Ok(());
+    }
+    let name = name.trim();
+    if name.is_empty() {
+        ctx.say("❌ The sound needs a name.").await?;
+        return Ok(());
+    }
+    let message = ctx.say("🔃 Downloading...").await?;
+    let result = match backend::prepare_sound(attachment).await {
+        Ok(sound) => {
+            let result = backend::library::add_library_sound(ctx.author().id, &sound, name).await;
+            if result.is_err() {
+                backend::attachments::discard_sound(&sound).await;
+            }
+            result
+        }
+        Err(why) => Err(why),
+    };
+    let content = match result {
+        Ok(_) => format!("✅ Added {name} to your library."),
+        Err(why) => format!("❌ Error: {why}"),
+    };
+    message
+        .edit(ctx, poise::CreateReply::default().content(content))
+        .await?;
+    Ok(())
+}
+
+/// List the sounds in your library.
+#[poise::command(prefix_command, slash_command, rename = "list")]
+#[instrument(
+    name="library_list",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn library_list(ctx: Context<'_>) -> Result<(), Error> {
+    info!("Listing library sounds");
+    ctx.defer_ephemeral().await?;
+    let sounds = backend::library::list_library_sounds(ctx.author().id);
+    let content = if sounds.is_empty() {
+        "Your library is empty. Add a sound with `/library add`.".to_string()
+    } else {
+        let lines = sounds
+            .iter()
+            .map(|sound| {
+                format!(
+                    "**{}**, used in {} places",
+                    sound.name,
+                    backend::library::count_assignments(sound)
+                )
+            })
+            .collect::<Vec<_>>();
+        format!("📚 Your library:\n{}", lines.join("\n"))
+    };
+    ctx.say(content).await?;
+    Ok(())
+}
+
+/// Use a sound from your library as a global or local sound.
+#[poise::command(prefix_command, slash_command, rename = "assign")]
+#[instrument(
+    name="library_assign",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn library_assign(
+    ctx: Context<'_>,
+    #[description = "Sound from your library."]
+    #[autocomplete = "autocomplete_library_sound"]
+    name: String,
+    #[description = "If true, the sound will only play in this server."]
+    #[flag]
+    local: bool,
+    #[description = "When the sound plays. Defaults to join."] kind: Option<SoundKind>,
+    #[description = "Only play the sound when joining this voice channel."]
+    #[channel_types("Voice")]
+    channel: Option<GuildChannel>,
+) -> Result<(), Error> {
+    info!("Assigning library sound");
+    let kind = kind.unwrap_or(SoundKind::Join);
+    let channel_id = channel.map(|channel| channel.id);
+    let local = local || channel_id.is_some();
+    ctx.defer_ephemeral().await?;
+    if changing_sounds_disabled() {
+        ctx.say("❌ Setting Joinsounds is temporarily disabled. Please try again shortly.")
+            .await?;
+        return Ok(());
+    }
+    let guild_id = match ctx.guild_id() {
+        Some(guild_id) if local => Some(guild_id),
+        None if local => {
+            ctx.say("❌ Must be in the target server to set local joinsound")
+                .await?;
+            return Ok(());
+        }
+        _ => None,
+    };
+    let content = match backend::library::assign_library_sound(
+        ctx.author().id,
+        &name,
+        guild_id,
+        channel_id,
+        kind,
+    ) {
+        Ok(_) => {
+            let scope = match channel_id {
+                Some(channel_id) => format!("<#{channel_id}>"),
+                None if local => "local".to_string(),
+                None => "global".to_string(),
+            };
+            format!(
+                "✅ {name} is now one of your {scope} {}s.",
+                sound_name(kind)
+            )
+        }
+        Err(why) => format!("❌ Error: {why}"),
+    };
+    ctx.say(content).await?;
+    Ok(())
+}
+
+/// Remove a sound from your library and everywhere it is used.
+#[poise::command(prefix_command, slash_command, rename = "remove")]
+#[instrument(
+    name="library_remove",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn library_remove(
+    ctx: Context<'_>,
+    #[description = "Sound from your library."]
+    #[autocomplete = "autocomplete_library_sound"]
+    name: String,
+) -> Result<(), Error> {
+    info!("Removing library sound");
+    ctx.defer_ephemeral().await?;
+    if changing_sounds_disabled() {
+        ctx.say("❌ Removing Joinsounds is temporarily disabled. Please try again shortly.")
+            .await?;
+        return Ok(());
+    }
+    let content = match backend::library::remove_library_sound(ctx.author().id, &name).await {
+        Ok(_) => format!("✅ Removed {name} from your library."),
+        Err(why) => format!("❌ Error: {why}"),
+    };
+    ctx.say(content).await?;
+    Ok(())
+}
+
+/// Set the timezone used for your time of day sounds.
+#[poise::command(prefix_command, slash_command, track_edits)]
+#[instrument(
+    name="timezone",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn timezone(
+    ctx: Context<'_>,
+    #[description = "Timezone name, like America/Toronto. Leave empty to see your timezone."]
+    #[autocomplete = "autocomplete_timezone"]
+    timezone: Option<String>,
+) -> Result<(), Error> {
+    info!("Setting user timezone");
+    ctx.defer_ephemeral().await?;
+    let content = match timezone {
+        None => format!(
+            "🕒 Your timezone is {}.",
+            backend::user_settings::get_timezone(ctx.author().id).name()
+        ),
+        Some(timezone) => match timezone.parse::<Tz>() {
+            Ok(timezone) => {
+                backend::user_settings::set_timezone(ctx.author().id, timezone);
+                format!("✅ Timezone set to {}.", timezone.name())
+            }
+            Err(_) => format!("❌ `{timezone}` is not a known timezone."),
+        },
+    };
+    ctx.say(content).await?;
+    Ok(())
+}
+
+/// Removes all user data and join sounds from the bot.
+#[poise::command(slash_command)]
+#[instrument(
+    name="purge",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn purge(ctx: Context<'_>) -> Result<(), Error> {
+    if changing_sounds_disabled() {
+        ctx.say("❌ Purging Joinsounds is temporarily disabled. Please try again shortly.")
+            .await?;
+        return Ok(());
+    }
+    let interaction_uuid = ctx.id();
+    let components = vec![CreateActionRow::Buttons(vec![CreateButton::new(format!(
+        "{interaction_uuid}"
+    ))
+    .style(ButtonStyle::Danger)
+    .emoji(ReactionType::from('🗑'))
+    .label("Delete all data")])];
+
+    ctx.send(poise::CreateReply::default()
+        .embed(poise::serenity_prelude::CreateEmbed::new()
+            .title("Purge Data")
+            .description("Are you sure you'd like to purge or delete all data? This is not reversible.")
+            .colour(colours::css::DANGER)
+        )
+        .components(components)
+        .ephemeral(true)
+    ).await?;
+
+    while let Some(mci) = ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .channel_id(ctx.channel_id())
+        .timeout(std::time::Duration::from_secs(120))
+        .filter(move |mci| mci.data.custom_id == interaction_uuid.to_string())
+        .await
+    {
+        ctx.defer().await?;
+        if let Err(why) = backend::remove_all_sounds(ctx.author().id).await {
+            ctx.send(
+                poise::CreateReply::default()
+                    .content(format!("Error when deleting data: {why}."))
+                    .ephemeral(true),
+            )
+            .await?;
+        } else {
+            ctx.send(
+                poise::CreateReply::default()
+                    .content("Data has been deleted.".to_string())
+                    .ephemeral(true),
+            )
+            .await?;
+        }
+
+        mci.create_response(ctx, CreateInteractionResponse::Acknowledge)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Find the voice channel a member is in, if they can play sounds in it right
+/// now.
+fn playable_voice_channel(
+    ctx: &serenity::client::Context,
+    guild_id: GuildId,
+    member: &Member,
+) -> Result<ChannelId, String> {
+    let voice_channel = ctx.cache.guild(guild_id).and_then(|guild| {
+        let channel_id = guild.voice_states.get(&member.user.id)?.channel_id?;
+        let channel = guild.channels.get(&channel_id)?;
+        Some((
+            channel_id,
+            guild.user_permissions_in(channel, member).speak(),
+        ))
+    });
+    match voice_channel {
+        None => Err("You need to be in a voice channel.".to_string()),
+        Some((_, false)) => Err("You can't speak in your voice channel.".to_string()),
+        Some((channel_id, true)) => {
+            let settings = backend::guild_settings::get_guild_settings(guild_id);
+            match event_listener::channel_skip_reason(ctx, &settings, guild_id, channel_id) {
+                Some(reason) => Err(format!("Sounds can't play here right now: {reason}.")),
+                None => Ok(channel_id),
+            }
+        }
+    }
+}
+
+/// Play your joinsound, or someone else's, in your voice channel.
+#[poise::command(prefix_command, slash_command, guild_only, member_cooldown = 10)]
+#[instrument(
+    name="play",
+    skip(ctx, user),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn play(
+    ctx: Context<'_>,
+    #[description = "Whose joinsound to play. Defaults to yours."] user: Option<User>,
+) -> Result<(), Error> {
+    info!("Playing sound on demand");
+    ctx.defer_ephemeral().await?;
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    let target = user.as_ref().unwrap_or(ctx.author());
+    let Some(member) = ctx.author_member().await else {
+        return Ok(());
+    };
+    let content = match playable_voice_channel(ctx.serenity_context(), guild_id, &member) {
+        Err(why) => format!("❌ {why}"),
+        Ok(channel_id) => {
+            match backend::peek_sound(target.id, guild_id, channel_id, SoundKind::Join).await {
+                Ok(path) => {
+                    let sound = backend::history::sound_name(&path);
+                    let result = playback::play_sound(
+                        ctx.serenity_context(),
+                        guild_id,
+                        channel_id,
+                        path,
+                        QueuedSound::recorded(
+                            Some(target.id),
+                            target.id,
+                            PlaySource::OnDemand,
+                            sound.clone(),
+                        ),
+                    )
+                    .await;
+                    if let Err(why) = &result {
+                        backend::history::record_play(
+                            guild_id,
+                            channel_id,
+                            target.id,
+                            PlaySource::OnDemand,
+                            Some(&sound),
+                            PlayOutcome::Failed(why.to_string()),
+                        );
+                    }
+                    match result {
+                        Ok(_) if target.id == ctx.author().id => {
+                            "✅ Playing your joinsound.".to_string()
+                        }
+                        Ok(_) => format!("✅ Playing {}'s joinsound.", target.name),
+                        Err(why) => format!("❌ Error: {why}"),
+                    }
+                }
+                Err(_) if target.id == ctx.author().id => {
+                    "❌ You don't have a joinsound. Add one with `/set`.".to_string()
+                }
+                Err(_) => format!("❌ {} doesn't have a joinsound.", target.name),
+            }
+        }
+    };
+    ctx.say(content).await?;
+    Ok(())
+}
+
+/// How long a member has to wait between presses on a soundboard panel.
+const SOUNDBOARD_PRESS_COOLDOWN: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// How long a soundboard panel keeps working.
+const SOUNDBOARD_PANEL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
+
+async fn autocomplete_soundboard_sound<'a>(
+    ctx: Context<'a>,
+    partial: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+    let partial = partial.to_lowercase();
+    ctx.guild_id()
+        .map(backend::soundboard::list_soundboard_sounds)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|sound| sound.name)
+        .filter(move |name| name.to_lowercase().contains(&partial))
+        .take(25)
+}
+
+/// Whether a member is an admin or has the role with the ID `role`.
+fn is_admin_or_has_role(ctx: Context<'_>, member: &Member, role: Option<String>) -> bool {
+    let is_admin = member
+        .permissions
+        .or_else(|| ctx.guild().map(|guild| guild.member_permissions(member)))
+        .is_some_and(|permissions| permissions.manage_guild());
+    is_admin || role.is_some_and(|role| member.roles.iter().any(|id| id.to_string() == role))
+}
+
+/// Whether a member may add and remove soundboard sounds, either as an admin
+/// or through the guild's soundboard role.
+fn can_manage_soundboard(ctx: Context<'_>, guild_id: GuildId, member: &Member) -> bool {
+    let role = backend::guild_settings::get_guild_settings(guild_id).soundboard_role_id;
+    is_admin_or_has_role(ctx, member, role)
+}
+
+async fn play_soundboard_sound(
+    ctx: &serenity::client::Context,
+    guild_id: GuildId,
+    member: &Member,
+    sound: &SoundboardSound,
+) -> String {
+    let channel_id = match playable_voice_channel(ctx, guild_id, member) {
+        Ok(channel_id) => channel_id,
+        Err(why) => return format!("❌ {why}"),
+    };
+    let result = match backend::soundboard::get_soundboard_file(sound).await {
+        Ok(path) => {
+            let queued = QueuedSound::recorded(
+                Some(member.user.id),
+                member.user.id,
+                PlaySource::Soundboard,
+                sound.name.clone(),
+            );
+            playback::play_sound(ctx, guild_id, channel_id, path, queued).await
+        }
+        Err(why) => Err(why),
+    };
+    if let Err(why) = &result {
+        backend::history::record_play(
+            guild_id,
+            channel_id,
+            member.user.id,
+            PlaySource::Soundboard,
+            Some(&sound.name),
+            PlayOutcome::Failed(why.to_string()),
+        );
+    }
+    match result {
+        Ok(_) => format!("✅ Playing {}.", sound.name),
+        Err(why) => format!("❌ Error: {why}"),
+    }
+}
+
+/// Play a sound from this server's soundboard, or show a panel of them.
+#[poise::command(prefix_command, slash_command, guild_only, member_cooldown = 5)]
+#[instrument(
+    name="soundboard",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn soundboard(
+    ctx: Context<'_>,
+    #[description = "Sound to play. Leave empty to show a panel of every sound."]
+    #[autocomplete = "autocomplete_soundboard_sound"]
+    name: Option<String>,
+) -> Result<(), Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+    if let Some(name) = name {
+        info!("Playing soundboard sound");
+        ctx.defer_ephemeral().await?;
+        let Some(member) = ctx.author_member().await else {
+            return Ok(());
+        };
+        let content = match backend::soundboard::get_soundboard_sound(guild_id, name.trim()) {
+            Some(sound) => {
+                play_soundboard_sound(ctx.serenity_context(), guild_id, &member, &sound).await
+            }
+            None => format!("❌ The soundboard doesn't have a sound called {name}."),
+        };
+        ctx.say(content).await?;
+        return Ok(());
+    }
+
+    info!("Showing soundboard panel");
+    let sounds = backend::soundboard::list_soundboard_sounds(guild_id);
+    if sounds.is_empty() {
+        ctx.send(
+            poise::CreateReply::default()
+                .content("The soundboard is empty. Add a sound with `/soundboard-library add`.")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+    let interaction_uuid = ctx.id();
+    let components = sounds
+        .chunks(5)
+        .map(|row| {
+            CreateActionRow::Buttons(
+                row.iter()
+                    .map(|sound| {
+                        CreateButton::new(format!("{interaction_uuid}-{}", sound.id))
+                            .style(ButtonStyle::Secondary)
+                            .label(&sound.name)
+                    })
+                    .collect(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let panel = ctx
+        .send(
+            poise::CreateReply::default()
+                .content("🔊 Press a button to play a sound in your voice channel.")
+                .components(components),
+        )
+        .await?;
+
+    let mut last_pressed = HashMap::new();
+    while let Some(mci) = ComponentInteractionCollector::new(ctx)
+        .channel_id(ctx.channel_id())
+        .timeout(SOUNDBOARD_PANEL_TIMEOUT)
+        .filter(move |mci| {
+            mci.data
+                .custom_id
+                .starts_with(&format!("{interaction_uuid}-"))
+        })
+        .await
+    {
+        let sound = mci
+            .data
+            .custom_id
+            .rsplit('-')
+            .next()
+            .and_then(|id| id.parse::<i32>().ok())
+            .and_then(|id| sounds.iter().find(|sound| sound.id == id));
+        let content = match (sound, &mci.member) {
+            (Some(_), _)
+                if last_pressed.get(&mci.user.id).is_some_and(
+                    |pressed: &std::time::Instant| pressed.elapsed() < SOUNDBOARD_PRESS_COOLDOWN,
+                ) =>
+            {
+                "❌ Slow down, try again in a few seconds.".to_string()
+            }
+            (Some(sound), Some(member)) => {
+                last_pressed.insert(mci.user.id, std::time::Instant::now());
+                play_soundboard_sound(ctx.serenity_context(), guild_id, member, sound).await
+            }
+            _ => "❌ That sound is no longer on the soundboard.".to_string(),
+        };
+        // One failed reply shouldn't leave the panel's buttons up after it expires
+        if let Err(why) = mci
+            .create_response(
+                ctx,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .ephemeral(true),
+                ),
+            )
+            .await
+        {
+            error!("Error responding to soundboard panel: {}", why);
+        }
+    }
+    panel
+        .edit(
+            ctx,
+            poise::CreateReply::default()
+                .content("🔇 This soundboard panel has expired, use `/soundboard` for a new one.")
+                .components(vec![]),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Manage the sounds on this server's soundboard.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "soundboard-library",
+    subcommands(
+        "soundboard_library_list",
+        "soundboard_library_add",
+        "soundboard_library_remove"
+    ),
+    subcommand_required
+)]
+async fn soundboard_library(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// List the sounds on this server's soundboard.
+#[poise::command(prefix_command, slash_command, rename = "list")]
+#[instrument(
+    name="soundboard_library_list",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn soundboard_library_list(ctx: Context<'_>) -> Result<(), Error> {
+    info!("Listing soundboard sounds");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let sounds = backend::soundboard::list_soundboard_sounds(guild_id);
+        let content = if sounds.is_empty() {
+            "The soundboard is empty. Add a sound with `/soundboard-library add`.".to_string()
+        } else {
+            sounds
+                .iter()
+                .map(|sound| match &sound.added_by {
+                    Some(user) => format!("**{}**, added by <@{user}>", sound.name),
+                    None => format!("**{}**", sound.name),
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        ctx.say(content).await?;
+    }
+    Ok(())
+}
+
+/// Upload a sound to this server's soundboard.
+#[poise::command(prefix_command, slash_command, rename = "add")]
+#[instrument(
+    name="soundboard_library_add",
+    skip(ctx, attachment),
+    fields(
+        user_id=%ctx.author(),
+        attachment_id=%attachment.id,
+    )
+)]
+async fn soundboard_library_add(
+    ctx: Context<'_>,
+    #[description = "Name to use for the sound."]
+    #[max_length = 80]
+    name: String,
+    #[description = "Sound to upload."] attachment: Attachment,
+) -> Result<(), Error> {
+    info!("Adding soundboard sound");
+    ctx.defer_ephemeral().await?;
+    let (Some(guild_id), Some(member)) = (ctx.guild_id(), ctx.author_member().await) else {
+        return Ok(());
+    };
+    if !can_manage_soundboard(ctx, guild_id, &member) {
+        ctx.say("❌ You don't have permission to change the soundboard.")
+            .await?;
+        return Ok(());
+    }
+    let name = name.trim();
+    if name.is_empty() {
+        ctx.say("❌ The sound needs a name.").await?;
+        return Ok(());
+    }
+    upload_guild_sound(
+        ctx,
+        guild_id,
+        attachment,
+        GuildSoundUpload::Soundboard { name },
+        format!("✅ Added {name} to the soundboard."),
+    )
+    .await
+}
+
+/// Remove a sound from this server's soundboard.
+#[poise::command(prefix_command, slash_command, rename = "remove")]
+#[instrument(
+    name="soundboard_library_remove",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn soundboard_library_remove(
+    ctx: Context<'_>,
+    #[description = "Sound to remove."]
+    #[autocomplete = "autocomplete_soundboard_sound"]
+    name: String,
+) -> Result<(), Error> {
+    info!("Removing soundboard sound");
+    ctx.defer_ephemeral().await?;
+    let (Some(guild_id), Some(member)) = (ctx.guild_id(), ctx.author_member().await) else {
+        return Ok(());
+    };
+    if !can_manage_soundboard(ctx, guild_id, &member) {
+        ctx.say("❌ You don't have permission to change the soundboard.")
+            .await?;
+        return Ok(());
+    }
+    let result = backend::soundboard::remove_soundboard_sound(guild_id, name.trim()).await;
+    ctx.say(result_message(
+        result,
+        format!("✅ Removed {name} from the soundboard."),
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Stop the sound that is playing and move on to the next one.
+#[poise::command(prefix_command, slash_command, guild_only)]
+#[instrument(
+    name="stop",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn stop(ctx: Context<'_>) -> Result<(), Error> {
+    info!("Stopping the current sound");
+    ctx.defer_ephemeral().await?;
+    let (Some(guild_id), Some(member)) = (ctx.guild_id(), ctx.author_member().await) else {
+        return Ok(());
+    };
+    let Some(track) = playback::current_sound(ctx.serenity_context(), guild_id).await else {
+        ctx.say("❌ Nothing is playing.").await?;
+        return Ok(());
+    };
+    let owner = track.data::<QueuedSound>().owner;
+    let role = backend::guild_settings::get_guild_settings(guild_id).stop_role_id;
+    let content = if owner != Some(ctx.author().id) && !is_admin_or_has_role(ctx, &member, role) {
+        "❌ You can only stop your own sounds.".to_string()
+    } else {
+        match track.stop() {
+            Ok(_) => "⏹️ Stopped the sound.".to_string(),
+            Err(why) => format!("❌ Error: {why}"),
+        }
+    };
+    ctx.say(content).await?;
+    Ok(())
+}
+
+/// Describe a play event for the history.
+fn format_play_event(event: &PlayEvent) -> String {
+    let sound = event.sound.as_deref().unwrap_or("no sound");
+    let outcome = match (event.outcome.as_str(), &event.detail) {
+        ("played", _) => String::new(),
+        (outcome, Some(detail)) => format!(", {outcome}: {detail}"),
+        (outcome, None) => format!(", {outcome}"),
+    };
+    format!(
+        "<t:{}:R> **{sound}** ({}) for <@{}> in <#{}>{outcome}",
+        event.played_at.and_utc().timestamp(),
+        event.source,
+        event.discord_id,
+        event.channel_id
+    )
+}
+
+/// Show the sounds played recently in this server.
+#[poise::command(prefix_command, slash_command, guild_only)]
+#[instrument(
+    name="history",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn history(
+    ctx: Context<'_>,
+    #[description = "How many sounds to show. Defaults to 10."]
+    #[min = 1]
+    #[max = 25]
+    count: Option<u32>,
+) -> Result<(), Error> {
+    info!("Showing play history");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let events = backend::history::get_play_history(guild_id, count.unwrap_or(10).into());
+        let content = if events.is_empty() {
+            "No sounds have played in this server yet.".to_string()
+        } else {
+            events
+                .iter()
+                .map(format_play_event)
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        ctx.send(
+            poise::CreateReply::default()
+                .content(content)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .ephemeral(true),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Find out whose sound played last in this server.
+#[poise::command(prefix_command, slash_command, guild_only, rename = "whoplayed")]
+#[instrument(
+    name="whoplayed",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn who_played(ctx: Context<'_>) -> Result<(), Error> {
+    info!("Showing the last played sound");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let playing = playback::current_sound(ctx.serenity_context(), guild_id)
+            .await
+            .and_then(|track| track.data::<QueuedSound>().play.clone());
+        let content = match (playing, backend::history::get_last_play(guild_id)) {
+            (Some(play), _) => format!(
+                "🔊 Playing now: **{}** ({}) for <@{}>",
+                play.sound,
+                play.source.as_str(),
+                play.user
+            ),
+            (None, Some(event)) => format_play_event(&event),
+            (None, None) => "No sounds have played in this server yet.".to_string(),
+        };
+        ctx.send(
+            poise::CreateReply::default()
+                .content(content)
+                .allowed_mentions(CreateAllowedMentions::new())
+                .ephemeral(true),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Describe the three hours of the day with the most plays.
+fn format_busiest_hours(hours: [i64; 24]) -> String {
+    let mut busiest = hours
+        .iter()
+        .enumerate()
+        .filter(|(_, plays)| **plays > 0)
+        .collect::<Vec<_>>();
+    busiest.sort_by_key(|(hour, plays)| (std::cmp::Reverse(**plays), *hour));
+    if busiest.is_empty() {
+        return "None yet".to_string();
+    }
+    busiest
+        .iter()
+        .take(3)
+        .map(|(hour, plays)| format!("{hour:02}:00–{:02}:00 ({plays} plays)", (hour + 1) % 24))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// See how joinsounds are used.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("stats_server", "stats_user", "stats_global"),
+    subcommand_required
+)]
+async fn stats(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// See how joinsounds are used in this server.
+#[poise::command(prefix_command, slash_command, guild_only, rename = "server")]
+#[instrument(
+    name="stats_server",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn stats_server(ctx: Context<'_>) -> Result<(), Error> {
+    info!("Showing server stats");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let timezone = backend::guild_settings::get_guild_settings(guild_id).timezone();
+        let most_heard = backend::stats::most_heard_members(guild_id, 5);
+        let most_heard = if most_heard.is_empty() {
+            "None yet".to_string()
+        } else {
+            most_heard
+                .iter()
+                .map(|(user, plays)| format!("<@{user}>: {plays} plays"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        ctx.send(
+            poise::CreateReply::default()
+                .embed(
+                    poise::serenity_prelude::CreateEmbed::new()
+                        .title("Server Stats")
+                        .field(
+                            "Sounds played",
+                            backend::stats::count_plays(Some(guild_id), None).to_string(),
+                            true,
+                        )
+                        .field(
+                            "Sounds stored",
+                            backend::stats::count_guild_sounds(guild_id).to_string(),
+                            true,
+                        )
+                        .field("Most heard members", most_heard, false)
+                        .field(
+                            format!("Busiest hours ({})", timezone.name()),
+                            format_busiest_hours(backend::stats::plays_by_hour(
+                                Some(guild_id),
+                                None,
+                                timezone,
+                            )),
+                            false,
+                        ),
+                )
+                .ephemeral(true),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// See how often your joinsounds play, or someone else's in this server.
+#[poise::command(prefix_command, slash_command, rename = "user")]
+#[instrument(
+    name="stats_user",
+    skip(ctx, user),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn stats_user(
+    ctx: Context<'_>,
+    #[description = "Whose stats to show. Defaults to yours."] user: Option<User>,
+) -> Result<(), Error> {
+    info!("Showing user stats");
+    ctx.defer_ephemeral().await?;
+    let user = user.as_ref().unwrap_or(ctx.author());
+    let embed =
+        poise::serenity_prelude::CreateEmbed::new().title(format!("Stats for {}", user.name));
+    let embed = if user.id == ctx.author().id {
+        let timezone = backend::user_settings::get_timezone(user.id);
+        let mut embed = embed.field(
+            "Sounds played everywhere",
+            backend::stats::count_plays(None, Some(user.id)).to_string(),
+            true,
+        );
+        if let Some(guild_id) = ctx.guild_id() {
+            embed = embed.field(
+                "Sounds played here",
+                backend::stats::count_plays(Some(guild_id), Some(user.id)).to_string(),
+                true,
+            );
+        }
+        embed
+            .field(
+                "Sounds stored",
+                backend::stats::count_user_sounds(user.id).to_string(),
+                true,
+            )
+            .field(
+                format!("Busiest hours ({})", timezone.name()),
+                format_busiest_hours(backend::stats::plays_by_hour(None, Some(user.id), timezone)),
+                false,
+            )
+    } else {
+        // Someone else's stats only cover this server, so nothing from other
+        // servers or their own settings is shared.
+        let Some(guild_id) = ctx.guild_id() else {
+            ctx.say("❌ Error: You can only see someone else's stats in a server.")
+                .await?;
+            return Ok(());
+        };
+        let timezone = backend::guild_settings::get_guild_settings(guild_id).timezone();
+        embed
+            .field(
+                "Sounds played here",
+                backend::stats::count_plays(Some(guild_id), Some(user.id)).to_string(),
+                true,
+            )
+            .field(
+                format!("Busiest hours ({})", timezone.name()),
+                format_busiest_hours(backend::stats::plays_by_hour(
+                    Some(guild_id),
+                    Some(user.id),
+                    timezone,
+                )),
+                false,
+            )
+    };
+    ctx.send(poise::CreateReply::default().embed(embed).ephemeral(true))
+        .await?;
+    Ok(())
+}
+
+/// See how joinsounds are used across every server.
+#[poise::command(prefix_command, slash_command, owners_only, rename = "global")]
+#[instrument(
+    name="stats_global",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn stats_global(ctx: Context<'_>) -> Result<(), Error> {
+    info!("Showing global stats");
+    ctx.defer_ephemeral().await?;
+    let (guilds, users) = backend::stats::count_active_guilds_and_users();
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(
+                poise::serenity_prelude::CreateEmbed::new()
+                    .title("Global Stats")
+                    .field(
+                        "Sounds played",
+                        backend::stats::count_plays(None, None).to_string(),
+                        true,
+                    )
+                    .field(
+                        "Sounds stored",
+                        backend::stats::count_all_sounds().to_string(),
+                        true,
+                    )
+                    .field("Servers with plays", guilds.to_string(), true)
+                    .field("Members with plays", users.to_string(), true)
+                    .field(
+                        "Busiest hours (UTC)",
+                        format_busiest_hours(backend::stats::plays_by_hour(None, None, Tz::UTC)),
+                        false,
+                    ),
+            )
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Force the bot to leave a voice channel.
+#[poise::command(prefix_command, slash_command, track_edits)]
+#[instrument(
+    name="leave",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn leave(ctx: Context<'_>) -> Result<(), Error> {
+    info!("Leaving voice channel");
+    ctx.defer_ephemeral().await?;
+    match ctx.say("🔃 Leaving...").await {
+        Ok(message) => {
+            if let Some(guild_id) = ctx.guild_id() {
+                let manager = songbird::get(ctx.serenity_context())
+                    .await
+                    .expect("Songbird Voice client placed in at initialisation.")
+                    .clone();
+                let has_handler = manager.get(guild_id).is_some();
+                playback::clear_waiting_sounds(ctx.serenity_context(), guild_id).await;
+                if has_handler {
                     if let Err(why) = manager.remove(guild_id).await {
                         error!("Error removing voice client: {}", why);
                         if let Err(why) = message
@@ -476,32 +1946,1238 @@ async fn leave(ctx: Context<'_>) -> Result<(), Error> {
                             poise::CreateReply::default().content("✅ Successful!".to_string()),
                         )
                         .await
-                    {
-                        error!("Error sending message: {}", why);
-                    }
-                } else if let Err(why) = message
-                    .edit(
-                        ctx,
-                        poise::CreateReply::default()
-                            .content("❌ Not in a voice channel.".to_string()),
+                    {
+                        error!("Error sending message: {}", why);
+                    }
+                } else if let Err(why) = message
+                    .edit(
+                        ctx,
+                        poise::CreateReply::default()
+                            .content("❌ Not in a voice channel.".to_string()),
+                    )
+                    .await
+                {
+                    error!("Error sending message: {}", why);
+                }
+            } else {
+                message
+                    .edit(
+                        ctx,
+                        poise::CreateReply::default()
+                            .content("❌ Not in a voice channel.".to_string()),
+                    )
+                    .await?;
+            }
+        }
+        Err(why) => error!("Error sending message: {}", why),
+    };
+
+    Ok(())
+}
+
+fn format_duration(duration: chrono::Duration) -> String {
+    let minutes = duration.num_minutes();
+    let seconds = duration.num_seconds() % 60;
+    if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// See when your joinsound will play again.
+#[poise::command(prefix_command, slash_command, guild_only)]
+#[instrument(
+    name="cooldown",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn cooldown(ctx: Context<'_>) -> Result<(), Error> {
+    info!("Checking cooldown");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let settings = backend::guild_settings::get_guild_settings(guild_id);
+        let cooldown = backend::cooldowns::get_cooldown(guild_id, ctx.author().id, &settings);
+        let remaining =
+            backend::cooldowns::remaining_cooldown(guild_id, ctx.author().id, &settings);
+        let content = match remaining {
+            Some(remaining) => format!(
+                "⏳ Your joinsound will play again in {}. The cooldown here is {}.",
+                format_duration(remaining),
+                format_duration(cooldown)
+            ),
+            None => format!(
+                "✅ Your joinsound is ready to play. The cooldown here is {}.",
+                format_duration(cooldown)
+            ),
+        };
+        ctx.say(content).await?;
+    }
+    Ok(())
+}
+
+/// Configure joinsounds for this server.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD",
+    subcommands(
+        "config_show",
+        "config_cooldown",
+        "config_idle",
+        "config_voice_events",
+        "config_voice_state_triggers",
+        "config_group_arrival",
+        "config_move_channels",
+        "config_busy",
+        "config_queue",
+        "config_theme_policy",
+        "config_soundboard_role",
+        "config_stop_role",
+        "config_skip_rules",
+        "config_channels",
+        "config_timezone",
+        "config_quiet_hours"
+    ),
+    subcommand_required
+)]
+async fn config(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show the joinsound settings for this server.
+#[poise::command(prefix_command, slash_command, rename = "show")]
+#[instrument(
+    name="config_show",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_show(ctx: Context<'_>) -> Result<(), Error> {
+    info!("Showing guild config");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let settings = backend::guild_settings::get_guild_settings(guild_id);
+        let channel_filters = backend::guild_settings::get_channel_filters(guild_id);
+        let allowed_channels = format_channel_list(&channel_filters, ChannelList::Allow);
+        let denied_channels = format_channel_list(&channel_filters, ChannelList::Deny);
+        let move_triggers =
+            format_channel_pairs(backend::guild_settings::get_move_triggers(guild_id));
+        let quiet_hours = format_quiet_hours(backend::guild_settings::get_quiet_hours(guild_id));
+        let quiet_hours_active = if backend::guild_settings::is_quiet_hours(guild_id, &settings) {
+            "Active now"
+        } else {
+            "Not active"
+        };
+        ctx.send(
+            poise::CreateReply::default()
+                .embed(
+                    poise::serenity_prelude::CreateEmbed::new()
+                        .title("Joinsound Settings")
+                        .field(
+                            "Cooldown",
+                            format_duration(chrono::Duration::seconds(
+                                settings.cooldown_seconds.into(),
+                            )),
+                            true,
+                        )
+                        .field(
+                            "Idle timeout",
+                            format_duration(chrono::Duration::minutes(
+                                settings.idle_timeout_minutes.into(),
+                            )),
+                            true,
+                        )
+                        .field(
+                            "Stay while occupied",
+                            settings.stay_while_occupied.to_string(),
+                            true,
+                        )
+                        .field("Play on join", settings.play_on_join.to_string(), true)
+                        .field("Play on move", settings.move_policy().name(), true)
+                        .field(
+                            "Voice state triggers",
+                            format!(
+                                "Going live: {}\nCamera on: {}\nUnmute: {}",
+                                settings.play_on_stream,
+                                settings.play_on_camera,
+                                settings.play_on_unmute
+                            ),
+                            true,
+                        )
+                        .field("Move channels", move_triggers, false)
+                        .field(
+                            "When busy in another channel",
+                            settings.busy_policy().name(),
+                            false,
+                        )
+                        .field(
+                            "Queue",
+                            format!(
+                                "Up to {} sounds, {} when full",
+                                settings.max_queue_length,
+                                settings.queue_drop_policy().name().to_lowercase()
+                            ),
+                            false,
+                        )
+                        .field(
+                            "Group arrival",
+                            match (settings.group_window_seconds, &settings.group_sound_path) {
+                                (0, _) => "Off".to_string(),
+                                (seconds, Some(_)) => format!("Within {seconds}s, group sound"),
+                                (seconds, None) => format!("Within {seconds}s, first member's sound"),
+                            },
+                            true,
+                        )
+                        .field(
+                            "Soundboard managers",
+                            match &settings.soundboard_role_id {
+                                Some(role) => format!("Admins and <@&{role}>"),
+                                None => "Admins".to_string(),
+                            },
+                            true,
+                        )
+                        .field(
+                            "Can stop any sound",
+                            match &settings.stop_role_id {
+                                Some(role) => format!("Admins and <@&{role}>"),
+                                None => "Admins".to_string(),
+                            },
+                            true,
+                        )
+                        .field(
+                            "Channel themes",
+                            settings.theme_policy().name(),
+                            false,
+                        )
+                        .field("Allowed channels", allowed_channels, true)
+                        .field("Denied channels", denied_channels, true)
+                        .field(
+                            "Skip rules",
+                            format!(
+                                "Ignore AFK channel: {}\nIgnore deafened members: {}\nIgnore server muted members: {}\nMinimum other members: {}",
+                                settings.ignore_afk_channel,
+                                settings.ignore_deafened,
+                                settings.ignore_muted,
+                                settings.min_other_members
+                            ),
+                            false,
+                        )
+                        .field(
+                            "Paused",
+                            match settings.paused_until() {
+                                Some(until) => format!("Until <t:{}:f>", until.and_utc().timestamp()),
+                                None => "No".to_string(),
+                            },
+                            true,
+                        )
+                        .field("Timezone", settings.timezone().name(), true)
+                        .field(
+                            format!("Quiet hours ({quiet_hours_active})"),
+                            quiet_hours,
+                            true,
+                        ),
+                )
+                .ephemeral(true),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Set how long to wait before someone's joinsound can play again.
+#[poise::command(prefix_command, slash_command, rename = "cooldown")]
+#[instrument(
+    name="config_cooldown",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_cooldown(
+    ctx: Context<'_>,
+    #[description = "Cooldown in seconds. Leave empty to reset it."]
+    #[max = 86400]
+    seconds: Option<u32>,
+    #[description = "Only change the cooldown for this member."] user: Option<User>,
+) -> Result<(), Error> {
+    info!("Setting cooldown");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let seconds = seconds.map(|seconds| seconds as i32);
+        let content = match user {
+            Some(user) => {
+                backend::cooldowns::set_member_cooldown(guild_id, user.id, seconds);
+                match seconds {
+                    Some(seconds) => format!("✅ Cooldown for {} set to {seconds}s.", user.name),
+                    None => format!(
+                        "✅ Cooldown for {} reset to the server cooldown.",
+                        user.name
+                    ),
+                }
+            }
+            None => {
+                let seconds = seconds.unwrap_or(backend::guild_settings::DEFAULT_COOLDOWN_SECONDS);
+                backend::guild_settings::set_cooldown(guild_id, seconds);
+                format!("✅ Server cooldown set to {seconds}s.")
+            }
+        };
+        ctx.say(content).await?;
+    }
+    Ok(())
+}
+
+/// Set how long the bot stays in a voice channel after the last sound.
+#[poise::command(prefix_command, slash_command, rename = "idle")]
+#[instrument(
+    name="config_idle",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_idle(
+    ctx: Context<'_>,
+    #[description = "Minutes to stay after the last sound. 0 leaves right away."]
+    #[max = 1440]
+    minutes: u32,
+    #[description = "If true, stay for as long as the channel has members."]
+    #[flag]
+    stay_while_occupied: bool,
+) -> Result<(), Error> {
+    info!("Setting idle timeout");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        backend::guild_settings::set_idle_timeout(guild_id, minutes as i32, stay_while_occupied);
+        let content = if stay_while_occupied {
+            format!("✅ The bot will stay while the channel has members, then leave after {minutes} minutes.")
+        } else {
+            format!("✅ The bot will leave {minutes} minutes after the last sound.")
+        };
+        ctx.say(content).await?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, poise::ChoiceParameter)]
+enum ListAction {
+    #[name = "Add"]
+    Add,
+    #[name = "Remove"]
+    Remove,
+}
+
+fn format_channel(channel: Option<ChannelId>) -> String {
+    match channel {
+        Some(channel) => format!("<#{channel}>"),
+        None => "any channel".to_string(),
+    }
+}
+
+fn format_channel_list(filters: &[(ChannelId, ChannelList)], list: ChannelList) -> String {
+    let channels = filters
+        .iter()
+        .filter(|(_, filter_list)| *filter_list == list)
+        .map(|(channel, _)| format!("<#{channel}>"))
+        .collect::<Vec<_>>();
+    if channels.is_empty() {
+        "None".to_string()
+    } else {
+        channels.join("\n")
+    }
+}
+
+fn format_channel_pairs(pairs: Vec<(Option<ChannelId>, Option<ChannelId>)>) -> String {
+    if pairs.is_empty() {
+        return "None".to_string();
+    }
+    pairs
+        .into_iter()
+        .map(|(from, to)| format!("{} → {}", format_channel(from), format_channel(to)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Choose which voice channel events play joinsounds.
+#[poise::command(prefix_command, slash_command, rename = "voice-events")]
+#[instrument(
+    name="config_voice_events",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_voice_events(
+    ctx: Context<'_>,
+    #[description = "Play a member's sound when they join a voice channel."] on_join: bool,
+    #[description = "Play a member's sound when they move to another voice channel."]
+    on_move: MovePolicy,
+) -> Result<(), Error> {
+    info!("Setting voice events");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        backend::guild_settings::set_voice_events(guild_id, on_join, on_move);
+        ctx.say(format!(
+            "✅ Play on join: {on_join}. Play on move: {}.",
+            on_move.name()
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Choose which voice state changes play a member's sound.
+#[poise::command(prefix_command, slash_command, rename = "voice-state-triggers")]
+#[instrument(
+    name="config_voice_state_triggers",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_voice_state_triggers(
+    ctx: Context<'_>,
+    #[description = "Play a member's stream sound when they go live."] on_stream: bool,
+    #[description = "Play a member's camera sound when they turn their camera on."] on_camera: bool,
+    #[description = "Play a member's unmute sound when they unmute."] on_unmute: bool,
+) -> Result<(), Error> {
+    info!("Setting voice state triggers");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        backend::guild_settings::set_voice_state_triggers(
+            guild_id, on_stream, on_camera, on_unmute,
+        );
+        ctx.say(format!(
+            "✅ Play on going live: {on_stream}. Play on camera on: {on_camera}. Play on unmute: {on_unmute}."
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Greet members who join a channel together with a single sound.
+#[poise::command(prefix_command, slash_command, rename = "group-arrival")]
+#[instrument(
+    name="config_group_arrival",
+    skip(ctx, sound),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_group_arrival(
+    ctx: Context<'_>,
+    #[description = "Group members who join within this many seconds. Use 0 to turn it off."]
+    #[max = 60]
+    seconds: u32,
+    #[description = "Sound to play for a group. Leave empty to keep the current one."]
+    sound: Option<Attachment>,
+    #[description = "Remove the group sound and play the first member's sound instead."]
+    clear_sound: Option<bool>,
+) -> Result<(), Error> {
+    info!("Setting group arrival");
+    ctx.defer_ephemeral().await?;
+    let clear_sound = clear_sound.unwrap_or(false);
+    if sound.is_some() && clear_sound {
+        ctx.say("❌ Error: Either upload a group sound or clear it, not both.")
+            .await?;
+        return Ok(());
+    }
+    if sound.is_some() && changing_sounds_disabled() {
+        ctx.say("❌ Setting Joinsounds is temporarily disabled. Please try again shortly.")
+            .await?;
+        return Ok(());
+    }
+    if let Some(guild_id) = ctx.guild_id() {
+        let result = match sound {
+            Some(attachment) => match backend::prepare_sound(attachment).await {
+                Ok(sound) => {
+                    let result = backend::guild_settings::set_group_arrival(
+                        guild_id,
+                        seconds as i32,
+                        Some(&sound),
+                        false,
+                    )
+                    .await;
+                    if result.is_err() {
+                        backend::attachments::discard_sound(&sound).await;
+                    }
+                    result
+                }
+                Err(why) => Err(why),
+            },
+            None => {
+                backend::guild_settings::set_group_arrival(
+                    guild_id,
+                    seconds as i32,
+                    None,
+                    clear_sound,
+                )
+                .await
+            }
+        };
+        let greeting = if backend::guild_settings::get_guild_settings(guild_id)
+            .group_sound_path
+            .is_some()
+        {
+            "a group sound"
+        } else {
+            "the first member's sound"
+        };
+        let content = match result {
+            Ok(_) if seconds == 0 => "✅ Members are greeted one at a time.".to_string(),
+            Ok(_) => format!(
+                "✅ Members who join within {} are greeted with {greeting}.",
+                format_duration(chrono::Duration::seconds(seconds.into()))
+            ),
+            Err(why) => format!("❌ Error: {why}"),
+        };
+        ctx.say(content).await?;
+    }
+    Ok(())
+}
+
+/// Let members with a role manage the soundboard, as well as admins.
+#[poise::command(prefix_command, slash_command, rename = "soundboard-role")]
+#[instrument(
+    name="config_soundboard_role",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_soundboard_role(
+    ctx: Context<'_>,
+    #[description = "Role that can add and remove soundboard sounds. Leave empty for admins only."]
+    role: Option<Role>,
+) -> Result<(), Error> {
+    info!("Setting soundboard role");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        backend::guild_settings::set_soundboard_role(guild_id, role.as_ref().map(|role| role.id));
+        let content = match role {
+            Some(role) => format!("✅ Members with <@&{}> can manage the soundboard.", role.id),
+            None => "✅ Only admins can manage the soundboard.".to_string(),
+        };
+        ctx.say(content).await?;
+    }
+    Ok(())
+}
+
+/// Let members with a role stop anyone's sound, as well as admins.
+#[poise::command(prefix_command, slash_command, rename = "stop-role")]
+#[instrument(
+    name="config_stop_role",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_stop_role(
+    ctx: Context<'_>,
+    #[description = "Role that can stop anyone's sound. Leave empty for admins only."] role: Option<
+        Role,
+    >,
+) -> Result<(), Error> {
+    info!("Setting stop role");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        backend::guild_settings::set_stop_role(guild_id, role.as_ref().map(|role| role.id));
+        let content = match role {
+            Some(role) => format!("✅ Members with <@&{}> can stop anyone's sound.", role.id),
+            None => "✅ Only admins can stop other members' sounds.".to_string(),
+        };
+        ctx.say(content).await?;
+    }
+    Ok(())
+}
+
+/// Add or remove channels that play joinsounds when members move between them.
+#[poise::command(prefix_command, slash_command, rename = "move-channels")]
+#[instrument(
+    name="config_move_channels",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_move_channels(
+    ctx: Context<'_>,
+    #[description = "Whether to add or remove this pair of channels."] action: ListAction,
+    #[description = "Channel the member moves from. Leave empty for any channel."]
+    #[channel_types("Voice")]
+    from: Option<GuildChannel>,
+    #[description = "Channel the member moves to. Leave empty for any channel."]
+    #[channel_types("Voice")]
+    to: Option<GuildChannel>,
+) -> Result<(), Error> {
+    info!("Editing move channels");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let from = from.map(|channel| channel.id);
+        let to = to.map(|channel| channel.id);
+        let pair = format!("{} → {}", format_channel(from), format_channel(to));
+        let content = match action {
+            ListAction::Add => {
+                backend::guild_settings::add_move_trigger(guild_id, from, to);
+                format!("✅ Added {pair}.")
+            }
+            ListAction::Remove => {
+                if backend::guild_settings::remove_move_trigger(guild_id, from, to) > 0 {
+                    format!("✅ Removed {pair}.")
+                } else {
+                    format!("❌ {pair} is not in the list.")
+                }
+            }
+        };
+        ctx.say(content).await?;
+    }
+    Ok(())
+}
+
+/// Choose what happens when the bot is busy in another voice channel.
+#[poise::command(prefix_command, slash_command, rename = "busy")]
+#[instrument(
+    name="config_busy",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_busy(
+    ctx: Context<'_>,
+    #[description = "What to do with sounds for another channel while the bot is busy."]
+    policy: BusyPolicy,
+) -> Result<(), Error> {
+    info!("Setting busy policy");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        backend::guild_settings::set_busy_policy(guild_id, policy);
+        ctx.say(format!(
+            "✅ When busy in another channel: {}.",
+            policy.name()
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Set how many sounds can wait to play in this server.
+#[poise::command(prefix_command, slash_command, rename = "queue")]
+#[instrument(
+    name="config_queue",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_queue(
+    ctx: Context<'_>,
+    #[description = "How many sounds can be queued at once."]
+    #[min = 1]
+    #[max = 50]
+    length: u32,
+    #[description = "Which sound to drop when the queue is full. Defaults to the new sound."]
+    drop_policy: Option<DropPolicy>,
+) -> Result<(), Error> {
+    info!("Setting queue");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let drop_policy = drop_policy.unwrap_or(DropPolicy::Newest);
+        backend::guild_settings::set_queue(guild_id, length as i32, drop_policy);
+        ctx.say(format!(
+            "✅ Up to {length} sounds can be queued. When full: {}.",
+            drop_policy.name()
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Choose between a channel's theme and a member's own sound.
+#[poise::command(prefix_command, slash_command, rename = "theme-policy")]
+#[instrument(
+    name="config_theme_policy",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_theme_policy(
+    ctx: Context<'_>,
+    #[description = "What to play when a member with a sound joins a channel with a theme."]
+    policy: ThemePolicy,
+) -> Result<(), Error> {
+    info!("Setting theme policy");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        backend::guild_settings::set_theme_policy(guild_id, policy);
+        ctx.say(format!(
+            "✅ When a member with a sound joins a channel with a theme: {}.",
+            policy.name()
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Choose when joinsounds should not play at all.
+#[poise::command(prefix_command, slash_command, rename = "skip-rules")]
+#[instrument(
+    name="config_skip_rules",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_skip_rules(
+    ctx: Context<'_>,
+    #[description = "Don't play sounds in the server's AFK channel."] ignore_afk_channel: bool,
+    #[description = "Don't play sounds for deafened members."] ignore_deafened: bool,
+    #[description = "Don't play sounds for server muted members."] ignore_muted: bool,
+    #[description = "How many other members must be in the channel."]
+    #[max = 99]
+    min_other_members: Option<u32>,
+) -> Result<(), Error> {
+    info!("Setting skip rules");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        backend::guild_settings::set_skip_rules(
+            guild_id,
+            ignore_afk_channel,
+            ignore_deafened,
+            ignore_muted,
+            min_other_members.unwrap_or(0) as i32,
+        );
+        ctx.say("✅ Skip rules updated.").await?;
+    }
+    Ok(())
+}
+
+/// Allow or deny joinsounds in specific voice channels or categories.
+#[poise::command(prefix_command, slash_command, rename = "channels")]
+#[instrument(
+    name="config_channels",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_channels(
+    ctx: Context<'_>,
+    #[description = "Whether to add or remove this channel."] action: ListAction,
+    #[description = "Voice channel or category."]
+    #[channel_types("Voice", "Category")]
+    channel: GuildChannel,
+    #[description = "List to add the channel to. Defaults to deny."] list: Option<ChannelList>,
+) -> Result<(), Error> {
+    info!("Editing channel lists");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let content = match action {
+            ListAction::Add => {
+                let list = list.unwrap_or(ChannelList::Deny);
+                backend::guild_settings::add_channel_filter(guild_id, channel.id, list);
+                format!("✅ Added <#{}> to the {} list.", channel.id, list.as_str())
+            }
+            ListAction::Remove => {
+                if backend::guild_settings::remove_channel_filter(guild_id, channel.id) > 0 {
+                    format!("✅ Removed <#{}>.", channel.id)
+                } else {
+                    format!("❌ <#{}> is not on a list.", channel.id)
+                }
+            }
+        };
+        ctx.say(content).await?;
+    }
+    Ok(())
+}
+
+fn format_quiet_hours(windows: Vec<(NaiveTime, NaiveTime)>) -> String {
+    if windows.is_empty() {
+        return "None".to_string();
+    }
+    windows
+        .into_iter()
+        .map(|(start, end)| format!("{} – {}", start.format("%H:%M"), end.format("%H:%M")))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+async fn autocomplete_timezone<'a>(
+    _ctx: Context<'_>,
+    partial: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+    let partial = partial.to_lowercase();
+    chrono_tz::TZ_VARIANTS
+        .iter()
+        .map(|timezone| timezone.name())
+        .filter(move |name| name.to_lowercase().contains(&partial))
+        .take(25)
+        .map(|name| name.to_string())
+}
+
+/// Set the timezone used for this server's quiet hours.
+#[poise::command(prefix_command, slash_command, rename = "timezone")]
+#[instrument(
+    name="config_timezone",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_timezone(
+    ctx: Context<'_>,
+    #[description = "Timezone name, like America/Toronto."]
+    #[autocomplete = "autocomplete_timezone"]
+    timezone: String,
+) -> Result<(), Error> {
+    info!("Setting timezone");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let content = match timezone.parse::<Tz>() {
+            Ok(timezone) => {
+                backend::guild_settings::set_timezone(guild_id, timezone);
+                format!("✅ Timezone set to {}.", timezone.name())
+            }
+            Err(_) => format!("❌ `{timezone}` is not a known timezone."),
+        };
+        ctx.say(content).await?;
+    }
+    Ok(())
+}
+
+/// Add or remove daily windows when joinsounds don't play.
+#[poise::command(prefix_command, slash_command, rename = "quiet-hours")]
+#[instrument(
+    name="config_quiet_hours",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn config_quiet_hours(
+    ctx: Context<'_>,
+    #[description = "Whether to add or remove this window."] action: ListAction,
+    #[description = "Start of the window in the server's timezone, like 23:00."] start: String,
+    #[description = "End of the window in the server's timezone, like 07:00."] end: String,
+) -> Result<(), Error> {
+    info!("Editing quiet hours");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let (Ok(start), Ok(end)) = (
+            NaiveTime::parse_from_str(start.trim(), "%H:%M"),
+            NaiveTime::parse_from_str(end.trim(), "%H:%M"),
+        ) else {
+            ctx.say("❌ Times must look like HH:MM, for example 23:00.")
+                .await?;
+            return Ok(());
+        };
+        let window = format_quiet_hours(vec![(start, end)]);
+        let content = match action {
+            ListAction::Add if start == end => {
+                "❌ Quiet hours must start and end at different times.".to_string()
+            }
+            ListAction::Add => {
+                backend::guild_settings::add_quiet_hours(guild_id, start, end);
+                format!("✅ Added quiet hours {window}.")
+            }
+            ListAction::Remove => {
+                if backend::guild_settings::remove_quiet_hours(guild_id, start, end) > 0 {
+                    format!("✅ Removed quiet hours {window}.")
+                } else {
+                    format!("❌ {window} is not in the quiet hours.")
+                }
+            }
+        };
+        ctx.say(content).await?;
+    }
+    Ok(())
+}
+
+/// Parse durations like `2h`, `45m` or `1d12h`.
+fn parse_duration(duration: &str) -> Option<chrono::Duration> {
+    let pattern = regex::Regex::new(r"^(?:(\d+)d)?(?:(\d+)h)?(?:(\d+)m)?(?:(\d+)s)?$").unwrap();
+    let duration = duration.trim().to_lowercase().replace(' ', "");
+    let captures = pattern.captures(&duration)?;
+    let part = |index| {
+        captures.get(index).map_or(Some(0), |amount: regex::Match| {
+            amount.as_str().parse::<i64>().ok()
+        })
+    };
+    let duration = chrono::Duration::try_days(part(1)?)?
+        .checked_add(&chrono::Duration::try_hours(part(2)?)?)?
+        .checked_add(&chrono::Duration::try_minutes(part(3)?)?)?
+        .checked_add(&chrono::Duration::try_seconds(part(4)?)?)?;
+    (duration > chrono::Duration::zero()).then_some(duration)
+}
+
+/// Pause joinsounds in this server for a while.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "pause-joinsounds",
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+#[instrument(
+    name="pause_joinsounds",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn pause_joinsounds(
+    ctx: Context<'_>,
+    #[description = "How long to pause for, like 30m, 2h or 1d."] duration: String,
+) -> Result<(), Error> {
+    info!("Pausing joinsounds");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let content = match parse_duration(&duration) {
+            Some(duration) if duration <= chrono::Duration::days(30) => {
+                let until = chrono::Utc::now() + duration;
+                backend::guild_settings::set_paused_until(guild_id, Some(until.naive_utc()));
+                playback::stop_all_sounds(ctx.serenity_context(), guild_id).await;
+                format!(
+                    "⏸️ Joinsounds are paused until <t:{}:f>.",
+                    until.timestamp()
+                )
+            }
+            Some(_) => "❌ Joinsounds can be paused for at most 30 days.".to_string(),
+            None => format!("❌ `{duration}` is not a valid duration, try something like 2h."),
+        };
+        ctx.say(content).await?;
+    }
+    Ok(())
+}
+
+/// Resume joinsounds in this server.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "resume-joinsounds",
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD"
+)]
+#[instrument(
+    name="resume_joinsounds",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn resume_joinsounds(ctx: Context<'_>) -> Result<(), Error> {
+    info!("Resuming joinsounds");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let settings = backend::guild_settings::get_guild_settings(guild_id);
+        let content = if settings.paused_until().is_some() {
+            backend::guild_settings::set_paused_until(guild_id, None);
+            "▶️ Joinsounds are playing again."
+        } else {
+            "❌ Joinsounds aren't paused."
+        };
+        ctx.say(content).await?;
+    }
+    Ok(())
+}
+
+/// What a sound uploaded for the whole server is used for.
+enum GuildSoundUpload<'a> {
+    Theme {
+        channel_id: ChannelId,
+        first_join_only: bool,
+    },
+    Default {
+        first_join_only: bool,
+    },
+    Soundboard {
+        name: &'a str,
+    },
+}
+
+/// Reply with `success`, or with the error that stopped it.
+fn result_message(result: Result<(), Error>, success: String) -> String {
+    match result {
+        Ok(_) => success,
+        Err(why) => format!("❌ Error: {why}"),
+    }
+}
+
+/// Download an attachment and store it as a server sound, then reply with
+/// `success`. The download is discarded if the sound can't be stored.
+async fn upload_guild_sound(
+    ctx: Context<'_>,
+    guild_id: GuildId,
+    attachment: Attachment,
+    upload: GuildSoundUpload<'_>,
+    success: String,
+) -> Result<(), Error> {
+    if changing_sounds_disabled() {
+        ctx.say("❌ Setting Joinsounds is temporarily disabled. Please try again shortly.")
+            .await?;
+        return Ok(());
+    }
+    let message = ctx.say("🔃 Downloading...").await?;
+    let result = match backend::prepare_sound(attachment).await {
+        Ok(sound) => {
+            let result = match upload {
+                GuildSoundUpload::Theme {
+                    channel_id,
+                    first_join_only,
+                } => {
+                    backend::themes::set_theme(guild_id, channel_id, &sound, first_join_only).await
+                }
+                GuildSoundUpload::Default { first_join_only } => {
+                    backend::default_sound::set_default_sound(guild_id, &sound, first_join_only)
+                        .await
+                }
+                GuildSoundUpload::Soundboard { name } => {
+                    backend::soundboard::add_soundboard_sound(
+                        guild_id,
+                        ctx.author().id,
+                        &sound,
+                        name,
                     )
                     .await
-                {
-                    error!("Error sending message: {}", why);
                 }
-            } else {
-                message
-                    .edit(
-                        ctx,
-                        poise::CreateReply::default()
-                            .content("❌ Not in a voice channel.".to_string()),
-                    )
-                    .await?;
+            };
+            if result.is_err() {
+                backend::attachments::discard_sound(&sound).await;
             }
+            result
         }
-        Err(why) => error!("Error sending message: {}", why),
+        Err(why) => Err(why),
     };
+    message
+        .edit(
+            ctx,
+            poise::CreateReply::default().content(result_message(result, success)),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Play a theme sound when members join a voice channel.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD",
+    subcommands("theme_set", "theme_view", "theme_remove"),
+    subcommand_required
+)]
+async fn theme(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set the theme sound for a voice channel.
+#[poise::command(prefix_command, slash_command, rename = "set")]
+#[instrument(
+    name="theme_set",
+    skip(ctx, attachment),
+    fields(
+        user_id=%ctx.author(),
+        attachment_id=%attachment.id,
+    )
+)]
+async fn theme_set(
+    ctx: Context<'_>,
+    #[description = "Voice channel to play the theme in."]
+    #[channel_types("Voice")]
+    channel: GuildChannel,
+    #[description = "Sound to play."] attachment: Attachment,
+    #[description = "Only play the theme when the first member joins. Defaults to false."]
+    first_join_only: Option<bool>,
+) -> Result<(), Error> {
+    info!("Setting channel theme");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        upload_guild_sound(
+            ctx,
+            guild_id,
+            attachment,
+            GuildSoundUpload::Theme {
+                channel_id: channel.id,
+                first_join_only: first_join_only.unwrap_or(false),
+            },
+            format!("✅ Set the theme for <#{}>.", channel.id),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Show the voice channels with a theme sound.
+#[poise::command(prefix_command, slash_command, rename = "view")]
+#[instrument(
+    name="theme_view",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn theme_view(ctx: Context<'_>) -> Result<(), Error> {
+    info!("Viewing channel themes");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let themes = backend::themes::list_themes(guild_id);
+        if themes.is_empty() {
+            ctx.say("No voice channels have a theme. Set one with `/theme set`.")
+                .await?;
+            return Ok(());
+        }
+        let lines = themes
+            .iter()
+            .map(|theme| {
+                let when = if theme.first_join_only {
+                    "when the first member joins"
+                } else {
+                    "whenever a member joins"
+                };
+                format!("<#{}>: plays {when}", theme.channel_id)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let policy = backend::guild_settings::get_guild_settings(guild_id).theme_policy();
+        ctx.say(format!(
+            "{lines}\nWhen a member has their own sound: {}.",
+            policy.name()
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Remove the theme sound from a voice channel.
+#[poise::command(prefix_command, slash_command, rename = "remove")]
+#[instrument(
+    name="theme_remove",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn theme_remove(
+    ctx: Context<'_>,
+    #[description = "Voice channel to remove the theme from."]
+    #[channel_types("Voice")]
+    channel: GuildChannel,
+) -> Result<(), Error> {
+    info!("Removing channel theme");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let result = backend::themes::remove_theme(guild_id, channel.id).await;
+        ctx.say(result_message(
+            result,
+            format!("✅ Removed the theme for <#{}>.", channel.id),
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Play a sound for members who don't have one of their own.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    guild_only,
+    rename = "default-sound",
+    required_permissions = "MANAGE_GUILD",
+    default_member_permissions = "MANAGE_GUILD",
+    subcommands("default_sound_set", "default_sound_view", "default_sound_remove"),
+    subcommand_required
+)]
+async fn default_sound(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Set the sound for members who don't have one of their own.
+#[poise::command(prefix_command, slash_command, rename = "set")]
+#[instrument(
+    name="default_sound_set",
+    skip(ctx, attachment),
+    fields(
+        user_id=%ctx.author(),
+        attachment_id=%attachment.id,
+    )
+)]
+async fn default_sound_set(
+    ctx: Context<'_>,
+    #[description = "Sound to play."] attachment: Attachment,
+    #[description = "Only play it the first time a member joins voice here. Defaults to false."]
+    first_join_only: Option<bool>,
+) -> Result<(), Error> {
+    info!("Setting default sound");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        upload_guild_sound(
+            ctx,
+            guild_id,
+            attachment,
+            GuildSoundUpload::Default {
+                first_join_only: first_join_only.unwrap_or(false),
+            },
+            "✅ Set the default sound for this server.".to_string(),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Show when the default sound plays.
+#[poise::command(prefix_command, slash_command, rename = "view")]
+#[instrument(
+    name="default_sound_view",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn default_sound_view(ctx: Context<'_>) -> Result<(), Error> {
+    info!("Viewing default sound");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let content = match backend::default_sound::get_default_sound(guild_id) {
+            Some(sound) if sound.first_join_only => {
+                "The default sound plays the first time a member without a sound joins voice here."
+            }
+            Some(_) => "The default sound plays whenever a member without a sound joins.",
+            None => "This server has no default sound. Set one with `/default-sound set`.",
+        };
+        ctx.say(content).await?;
+    }
+    Ok(())
+}
 
+/// Stop playing a sound for members who don't have one of their own.
+#[poise::command(prefix_command, slash_command, rename = "remove")]
+#[instrument(
+    name="default_sound_remove",
+    skip(ctx),
+    fields(
+        user_id=%ctx.author(),
+    )
+)]
+async fn default_sound_remove(ctx: Context<'_>) -> Result<(), Error> {
+    info!("Removing default sound");
+    ctx.defer_ephemeral().await?;
+    if let Some(guild_id) = ctx.guild_id() {
+        let result = backend::default_sound::remove_default_sound(guild_id).await;
+        ctx.say(result_message(
+            result,
+            "✅ Removed the default sound for this server.".to_string(),
+        ))
+        .await?;
+    }
     Ok(())
 }
 
@@ -576,6 +3252,19 @@ async fn privacy_policy(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+/// How often scheduled sounds that have ended are cleaned up.
+const EXPIRED_SOUND_CHECK_PERIOD: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+async fn remove_expired_sounds() {
+    let mut interval = tokio::time::interval(EXPIRED_SOUND_CHECK_PERIOD);
+    loop {
+        interval.tick().await;
+        if let Err(why) = backend::remove_expired_sounds().await {
+            error!("Error removing expired sounds: {}", why);
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
     dotenv::dotenv().ok();
@@ -663,6 +3352,7 @@ async fn main() {
                     }
                     _ => {}
                 }
+                tokio::spawn(remove_expired_sounds());
 
                 Ok(())
             })
@@ -680,8 +3370,25 @@ async fn main() {
                 view(),
                 remove(),
                 remove_local(),
+                rotation(),
+                weight(),
+                library(),
+                timezone(),
+                play(),
+                soundboard(),
+                soundboard_library(),
+                stop(),
+                history(),
+                who_played(),
+                stats(),
                 purge(),
                 leave(),
+                cooldown(),
+                config(),
+                pause_joinsounds(),
+                resume_joinsounds(),
+                theme(),
+                default_sound(),
                 support(),
                 tos(),
                 privacy_policy(),
@@ -699,6 +3406,140 @@ async fn main() {
     let client = poise::serenity_prelude::ClientBuilder::new(token, intents)
         .framework(framework)
         .register_songbird()
+        .type_map_insert::<playback::WaitingSounds>(Default::default())
+        .type_map_insert::<event_listener::PendingArrivals>(Default::default())
         .await;
     client.unwrap().start().await.unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_adds_up_units() {
+        assert_eq!(parse_duration("45m"), Some(chrono::Duration::minutes(45)));
+        assert_eq!(parse_duration("30s"), Some(chrono::Duration::seconds(30)));
+        assert_eq!(parse_duration("1d12h"), Some(chrono::Duration::hours(36)));
+        assert_eq!(
+            parse_duration(" 1D 2h 3M 4s "),
+            Some(chrono::Duration::seconds(93784))
+        );
+    }
+
+    #[test]
+    fn parse_duration_rejects_zero() {
+        assert_eq!(parse_duration("0m"), None);
+        assert_eq!(parse_duration("0d0h0m0s"), None);
+        assert_eq!(parse_duration(""), None);
+    }
+
+    #[test]
+    fn parse_duration_rejects_huge_values() {
+        assert_eq!(parse_duration("99999999999999999999d"), None);
+        assert_eq!(parse_duration("9999999999999d"), None);
+        assert_eq!(parse_duration("106751991167d24h"), None);
+    }
+
+    #[test]
+    fn parse_duration_rejects_other_input() {
+        for duration in ["2", "h", "2x", "-5m", "1.5h", "5m2h", "1h1h"] {
+            assert_eq!(parse_duration(duration), None, "{duration}");
+        }
+    }
+
+    fn schedule(
+        from: Option<&str>,
+        until: Option<&str>,
+        days: Option<u32>,
+    ) -> Result<Schedule, String> {
+        parse_schedule(
+            from.map(str::to_string),
+            until.map(str::to_string),
+            days,
+            None,
+            None,
+            None,
+        )
+    }
+
+    fn midnight(date: &str) -> chrono::NaiveDateTime {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .unwrap()
+            .and_time(NaiveTime::MIN)
+    }
+
+    #[test]
+    fn parse_schedule_includes_the_end_date() {
+        let schedule = schedule(Some("2999-01-01"), Some("2999-01-01"), None).unwrap();
+        assert_eq!(schedule.starts_at, Some(midnight("2999-01-01")));
+        assert_eq!(schedule.ends_at, Some(midnight("2999-01-02")));
+    }
+
+    #[test]
+    fn parse_schedule_rejects_ending_before_the_start() {
+        assert!(schedule(Some("2999-01-10"), Some("2999-01-05"), None).is_err());
+        assert!(schedule(None, Some("2000-01-01"), None).is_err());
+    }
+
+    #[test]
+    fn parse_schedule_counts_days_from_the_start() {
+        let schedule = schedule(Some("2999-01-01"), None, Some(3)).unwrap();
+        assert_eq!(schedule.ends_at, Some(midnight("2999-01-04")));
+    }
+
+    #[test]
+    fn parse_schedule_counts_days_from_now() {
+        let before = chrono::Utc::now().naive_utc();
+        let ends_at = schedule(None, None, Some(2)).unwrap().ends_at.unwrap();
+        let after = chrono::Utc::now().naive_utc();
+        assert!(before + chrono::Duration::days(2) <= ends_at);
+        assert!(ends_at <= after + chrono::Duration::days(2));
+    }
+
+    #[test]
+    fn parse_schedule_rejects_until_and_days_together() {
+        assert!(schedule(None, Some("2999-01-01"), Some(3)).is_err());
+    }
+
+    #[test]
+    fn parse_schedule_rejects_bad_dates() {
+        assert!(schedule(Some("tomorrow"), None, None).is_err());
+        assert!(schedule(None, Some("2999-13-01"), None).is_err());
+    }
+
+    #[test]
+    fn parse_weekdays_sets_a_bit_per_day() {
+        assert_eq!(parse_weekdays("mon"), Ok(0b0000001));
+        assert_eq!(parse_weekdays("mon,fri"), Ok(0b0010001));
+        assert_eq!(parse_weekdays(" Sunday , TUE "), Ok(0b1000010));
+        assert_eq!(parse_weekdays("weekends"), Ok(0b1100000));
+        assert_eq!(parse_weekdays("weekdays,sat"), Ok(0b0111111));
+    }
+
+    #[test]
+    fn parse_weekdays_rejects_bad_names() {
+        assert!(parse_weekdays("funday").is_err());
+        assert!(parse_weekdays("mon,,fri").is_err());
+        assert!(parse_weekdays("").is_err());
+    }
+
+    #[test]
+    fn parse_schedule_checks_times_of_day() {
+        let schedule = |after: &str, before: &str| {
+            parse_schedule(
+                None,
+                None,
+                None,
+                Some(after.to_string()),
+                Some(before.to_string()),
+                None,
+            )
+        };
+        assert!(schedule("08:00", "08:00").is_err());
+        assert!(schedule("8am", "09:00").is_err());
+        let schedule = schedule("22:00", "02:00").unwrap();
+        assert_eq!(schedule.time_start, NaiveTime::from_hms_opt(22, 0, 0));
+        assert_eq!(schedule.time_end, NaiveTime::from_hms_opt(2, 0, 0));
+    }
+}
@@ -0,0 +1,513 @@
+use serenity::all::{ChannelId, GuildId, UserId};
+use serenity::async_trait;
+use serenity::prelude::{Mutex, TypeMapKey};
+use songbird::{
+    tracks::{Track, TrackHandle},
+    Call, EventContext as SongbirdEventContext, EventHandler as SongbirdEventHandler,
+};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, info, instrument, warn};
+
+use super::backend;
+use super::backend::guild_settings::{BusyPolicy, DropPolicy};
+use super::backend::history::{PlayOutcome, PlaySource};
+use super::backend::models::GuildSettings;
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// How often a connected call checks whether it has been idle for too long.
+const IDLE_CHECK_PERIOD: Duration = Duration::from_secs(30);
+
+/// Count the members in a voice channel, not including bots.
+pub fn humans_in_channel(
+    ctx: &serenity::client::Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> usize {
+    other_humans_in_channel(ctx, guild_id, channel_id, ctx.cache.current_user().id)
+}
+
+/// Count the members in a voice channel other than `user_id`, not including
+/// bots.
+pub fn other_humans_in_channel(
+    ctx: &serenity::client::Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    user_id: UserId,
+) -> usize {
+    let current_user_id = ctx.cache.current_user().id;
+    ctx.cache
+        .guild(guild_id)
+        .map(|guild| {
+            guild
+                .voice_states
+                .values()
+                .filter(|voice_state| voice_state.channel_id == Some(channel_id))
+                .filter(|voice_state| {
+                    voice_state.user_id != current_user_id && voice_state.user_id != user_id
+                })
+                .filter(|voice_state| {
+                    voice_state
+                        .member
+                        .as_ref()
+                        .is_none_or(|member| !member.user.bot)
+                })
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+/// Who a queued sound is for, kept as the track's data.
+#[derive(Debug, Clone)]
+pub struct QueuedSound {
+    /// The member the sound belongs to, who is allowed to stop it.
+    pub owner: Option<UserId>,
+    /// How the sound shows up in the play history once it starts, if at all.
+    pub play: Option<PlayRecord>,
+}
+
+impl QueuedSound {
+    /// A sound for `user` that is added to the play history when it starts.
+    pub fn recorded(
+        owner: Option<UserId>,
+        user: UserId,
+        source: PlaySource,
+        sound: String,
+    ) -> QueuedSound {
+        QueuedSound {
+            owner,
+            play: Some(PlayRecord {
+                user,
+                source,
+                sound,
+            }),
+        }
+    }
+}
+
+/// A play history entry waiting for its sound to start.
+#[derive(Debug, Clone)]
+pub struct PlayRecord {
+    pub user: UserId,
+    pub source: PlaySource,
+    pub sound: String,
+}
+
+/// Add a sound that just started to the guild's play history.
+fn record_start(guild_id: GuildId, channel_id: ChannelId, sound: &QueuedSound) {
+    if let Some(play) = &sound.play {
+        backend::history::record_play(
+            guild_id,
+            channel_id,
+            play.user,
+            play.source,
+            Some(&play.sound),
+            PlayOutcome::Played,
+        );
+    }
+}
+
+/// Get the sound playing in a guild right now.
+pub async fn current_sound(
+    ctx: &serenity::client::Context,
+    guild_id: GuildId,
+) -> Option<TrackHandle> {
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+    let handler_lock = manager.get(guild_id)?;
+    let handler = handler_lock.lock().await;
+    handler.queue().current()
+}
+
+/// Sounds for other channels, waiting for the bot to finish in its current
+/// channel.
+pub struct WaitingSounds;
+
+impl TypeMapKey for WaitingSounds {
+    type Value = Arc<Mutex<HashMap<GuildId, VecDeque<(ChannelId, PathBuf, QueuedSound)>>>>;
+}
+
+async fn waiting_sounds(
+    ctx: &serenity::client::Context,
+) -> Arc<Mutex<HashMap<GuildId, VecDeque<(ChannelId, PathBuf, QueuedSound)>>>> {
+    ctx.data
+        .read()
+        .await
+        .get::<WaitingSounds>()
+        .cloned()
+        .expect("Waiting sounds placed in at initialisation.")
+}
+
+/// Forget any sounds waiting for the bot to change channels in a guild.
+pub async fn clear_waiting_sounds(ctx: &serenity::client::Context, guild_id: GuildId) {
+    waiting_sounds(ctx).await.lock().await.remove(&guild_id);
+}
+
+/// Check whether the bot has nothing playing, queued or waiting in a guild.
+pub async fn is_idle(ctx: &serenity::client::Context, guild_id: GuildId) -> bool {
+    let waiting = waiting_sounds(ctx)
+        .await
+        .lock()
+        .await
+        .get(&guild_id)
+        .is_some_and(|waiting| !waiting.is_empty());
+    if waiting {
+        return false;
+    }
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+    match manager.get(guild_id) {
+        Some(handler_lock) => handler_lock.lock().await.queue().is_empty(),
+        None => true,
+    }
+}
+
+/// Stop the sound playing in a guild and drop everything queued or waiting
+/// after it.
+pub async fn stop_all_sounds(ctx: &serenity::client::Context, guild_id: GuildId) {
+    clear_waiting_sounds(ctx, guild_id).await;
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+    if let Some(handler_lock) = manager.get(guild_id) {
+        handler_lock.lock().await.queue().stop();
+    }
+}
+
+async fn wait_for_channel(
+    ctx: &serenity::client::Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    path: PathBuf,
+    sound: QueuedSound,
+    max_queue_length: i32,
+) -> Result<(), Error> {
+    let waiting_sounds = waiting_sounds(ctx).await;
+    let mut waiting_sounds = waiting_sounds.lock().await;
+    let guild_waiting = waiting_sounds.entry(guild_id).or_default();
+    if guild_waiting.len() >= max_queue_length.max(1) as usize {
+        warn!("Too many sounds waiting, dropping the new sound");
+        return Err(Box::new(std::io::Error::other("The sound queue is full")));
+    }
+    info!("Busy in another channel, waiting to play sound");
+    guild_waiting.push_back((channel_id, path, sound));
+    Ok(())
+}
+
+/// Move to the channel of the oldest waiting sound and queue every sound
+/// waiting for that channel. Returns whether there was anything waiting.
+async fn play_waiting_sounds(ctx: &serenity::client::Context, guild_id: GuildId) -> bool {
+    let sounds = {
+        let waiting_sounds = waiting_sounds(ctx).await;
+        let mut waiting_sounds = waiting_sounds.lock().await;
+        let Some(guild_waiting) = waiting_sounds.get_mut(&guild_id) else {
+            return false;
+        };
+        let Some((next_channel, _, _)) = guild_waiting.front().cloned() else {
+            return false;
+        };
+        let (sounds, rest): (VecDeque<_>, VecDeque<_>) = guild_waiting
+            .drain(..)
+            .partition(|(channel_id, _, _)| *channel_id == next_channel);
+        *guild_waiting = rest;
+        sounds
+    };
+    for (channel_id, path, sound) in sounds {
+        if let Err(why) = play_sound(ctx, guild_id, channel_id, path, sound).await {
+            error!("Error playing waiting sound: {}", why);
+        }
+    }
+    true
+}
+
+/// Join `channel_id` if the bot is not connected in the guild yet, then queue
+/// the sound at `path`. Sounds in a guild play in order. If the bot is busy in
+/// another channel, the guild's busy policy decides whether it moves, waits or
+/// skips the sound. Once the queue is empty the bot leaves, or stays around
+/// according to the guild's idle settings. The sound is added to the play
+/// history when it starts.
+#[instrument(skip(ctx))]
+pub async fn play_sound(
+    ctx: &serenity::client::Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    path: PathBuf,
+    sound: QueuedSound,
+) -> Result<(), Error> {
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+    let settings = backend::guild_settings::get_guild_settings(guild_id);
+
+    let mut connect = false;
+    if let Some(handler_mutex) = manager.get(guild_id) {
+        let mut handler = handler_mutex.lock().await;
+
+        match handler.current_channel() {
+            None => connect = true,
+            Some(current_channel) if current_channel != channel_id.into() => {
+                if handler.queue().is_empty() {
+                    // Nothing is playing in the old channel, so just move over
+                    connect = true;
+                } else {
+                    match settings.busy_policy() {
+                        BusyPolicy::Move => {
+                            info!("Moving away from a busy channel");
+                            handler.queue().stop();
+                            // Switch channels before releasing the lock, so the
+                            // stopped sounds don't make the bot leave
+                            let join = handler.join(channel_id).await?;
+                            drop(handler);
+                            join.await?;
+                        }
+                        BusyPolicy::Queue => {
+                            drop(handler);
+                            return wait_for_channel(
+                                ctx,
+                                guild_id,
+                                channel_id,
+                                path,
+                                sound,
+                                settings.max_queue_length,
+                            )
+                            .await;
+                        }
+                        BusyPolicy::Skip => {
+                            warn!("Busy in another channel, skipping sound");
+                            return Err(Box::new(std::io::Error::other(
+                                "Already playing sounds in another channel",
+                            )));
+                        }
+                    }
+                }
+            }
+            Some(_) => {}
+        }
+    } else {
+        connect = true;
+    }
+    if connect {
+        if let Ok(handler_lock) = manager.join(guild_id, channel_id).await {
+            let mut handler = handler_lock.lock().await;
+            handler.remove_all_global_events();
+            handler.add_global_event(
+                songbird::events::Event::Periodic(IDLE_CHECK_PERIOD, None),
+                IdleNotifier {
+                    ctx: ctx.clone(),
+                    guild_id,
+                    call: handler_lock.clone(),
+                    idle_since: std::sync::Mutex::new(None),
+                    settings: std::sync::Mutex::new(None),
+                },
+            );
+        }
+    }
+
+    if let Some(handler_lock) = manager.get(guild_id) {
+        let mut track = Track::new_with_data(
+            songbird::input::File::new(path).into(),
+            Arc::new(sound.clone()),
+        );
+        let mut handler = handler_lock.lock().await;
+
+        let queue = handler.queue();
+        if queue.len() >= settings.max_queue_length.max(1) as usize {
+            let dropped = if settings.queue_drop_policy() == DropPolicy::Oldest && queue.len() > 1 {
+                queue.dequeue(1)
+            } else {
+                None
+            };
+            if let Some(dropped) = dropped {
+                warn!("Queue is full, dropping the oldest queued sound");
+                if let Some(play) = &dropped.data::<QueuedSound>().play {
+                    backend::history::record_play(
+                        guild_id,
+                        channel_id,
+                        play.user,
+                        play.source,
+                        Some(&play.sound),
+                        PlayOutcome::Skipped("queue full".to_string()),
+                    );
+                }
+                if let Err(why) = dropped.stop() {
+                    error!("Error stopping dropped sound: {}", why);
+                }
+            } else {
+                warn!("Queue is full, dropping the new sound");
+                return Err(Box::new(std::io::Error::other("The sound queue is full")));
+            }
+        }
+        // Queued sounds start paused, and only send a play event once their
+        // turn comes. A sound for an empty queue starts right away.
+        let starts_now = queue.is_empty();
+        if !starts_now {
+            track.events.add_event(
+                songbird::events::EventData::new(
+                    songbird::events::Event::Track(songbird::events::TrackEvent::Play),
+                    SongStartNotifier {
+                        guild_id,
+                        channel_id,
+                    },
+                ),
+                Duration::ZERO,
+            );
+        }
+        let track_handler = handler.enqueue(track).await;
+        if starts_now {
+            record_start(guild_id, channel_id, &sound);
+        }
+
+        if let Err(why) = track_handler.add_event(
+            songbird::events::Event::Track(songbird::events::TrackEvent::End),
+            SongEndNotifier {
+                ctx: ctx.clone(),
+                guild_id,
+                channel_id,
+                call: handler_lock.clone(),
+                settings: settings.clone(),
+            },
+        ) {
+            error!("Cannot add event: {}", why);
+        }
+        Ok(())
+    } else {
+        Err(Box::new(std::io::Error::other(
+            "Could not connect to the voice channel",
+        )))
+    }
+}
+
+/// Records a queued sound in the play history once it starts.
+#[derive(Debug)]
+struct SongStartNotifier {
+    guild_id: GuildId,
+    channel_id: ChannelId,
+}
+
+#[async_trait]
+impl SongbirdEventHandler for SongStartNotifier {
+    #[instrument(name = "songbird-start-notifier", skip(ctx))]
+    async fn act(&self, ctx: &SongbirdEventContext<'_>) -> Option<songbird::events::Event> {
+        if let SongbirdEventContext::Track(tracks) = ctx {
+            for (_, handle) in tracks.iter() {
+                record_start(
+                    self.guild_id,
+                    self.channel_id,
+                    &handle.data::<QueuedSound>(),
+                );
+            }
+        }
+        // Only the first start counts as a play
+        Some(songbird::events::Event::Cancel)
+    }
+}
+
+#[derive(Debug)]
+struct SongEndNotifier {
+    ctx: serenity::client::Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    call: Arc<Mutex<Call>>,
+    /// The guild's settings when the sound was queued.
+    settings: GuildSettings,
+}
+
+#[async_trait]
+impl SongbirdEventHandler for SongEndNotifier {
+    #[instrument(name = "songbird-end-notifier", skip(_ctx))]
+    async fn act(&self, _ctx: &SongbirdEventContext<'_>) -> Option<songbird::events::Event> {
+        let handler = (*self.call).lock().await;
+        // The bot has moved on to another channel since this sound was queued
+        if handler.current_channel() != Some(self.channel_id.into()) {
+            return None;
+        }
+        // The queue's own handler runs first and starts the next sound, so
+        // only leave once nothing else is waiting.
+        if !handler.queue().is_empty() {
+            info!("{} sounds left in the queue", handler.queue().len());
+            return None;
+        }
+        drop(handler);
+        if play_waiting_sounds(&self.ctx, self.guild_id).await {
+            return None;
+        }
+
+        let mut handler = (*self.call).lock().await;
+        let settings = &self.settings;
+        if settings.idle_timeout_minutes > 0 || settings.stay_while_occupied {
+            info!("staying in the channel until idle");
+            return None;
+        }
+        info!("leaving now");
+        if let Err(why) = handler.leave().await {
+            error!("Error leaving voice channel: {:?}", why);
+        }
+
+        return None;
+    }
+}
+
+/// Leaves the call once it has had nothing to play for longer than the guild's
+/// idle timeout, unless it should stay while the channel has members.
+#[derive(Debug)]
+struct IdleNotifier {
+    ctx: serenity::client::Context,
+    guild_id: GuildId,
+    call: Arc<Mutex<Call>>,
+    idle_since: std::sync::Mutex<Option<Instant>>,
+    /// The guild's settings, loaded once each time the call goes idle.
+    settings: std::sync::Mutex<Option<GuildSettings>>,
+}
+
+#[async_trait]
+impl SongbirdEventHandler for IdleNotifier {
+    #[instrument(name = "songbird-idle-notifier", skip(self, _ctx), fields(guild_id=%self.guild_id))]
+    async fn act(&self, _ctx: &SongbirdEventContext<'_>) -> Option<songbird::events::Event> {
+        let mut handler = (*self.call).lock().await;
+        let channel_id = ChannelId::from(handler.current_channel()?.0);
+        if !handler.queue().is_empty() {
+            *self.idle_since.lock().unwrap() = None;
+            *self.settings.lock().unwrap() = None;
+            return None;
+        }
+
+        let settings = self
+            .settings
+            .lock()
+            .unwrap()
+            .get_or_insert_with(|| backend::guild_settings::get_guild_settings(self.guild_id))
+            .clone();
+        if settings.stay_while_occupied
+            && humans_in_channel(&self.ctx, self.guild_id, channel_id) > 0
+        {
+            *self.idle_since.lock().unwrap() = None;
+            return None;
+        }
+
+        let idle_timeout = Duration::from_secs(settings.idle_timeout_minutes.max(0) as u64 * 60);
+        let idle_for = self
+            .idle_since
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Instant::now)
+            .elapsed();
+        if idle_for >= idle_timeout {
+            info!("idle for {:?}, leaving now", idle_for);
+            *self.idle_since.lock().unwrap() = None;
+            if let Err(why) = handler.leave().await {
+                error!("Error leaving voice channel: {:?}", why);
+            }
+        }
+
+        None
+    }
+}
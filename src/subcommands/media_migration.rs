@@ -16,24 +16,19 @@ use tokio::fs::create_dir_all;
 use tokio::fs::OpenOptions;
 use tokio::io::AsyncWriteExt;
 
-type JoinsoundEntry = (
-    i32,
-    Option<String>,
-    Option<String>,
-    Option<String>,
-    Option<chrono::NaiveDateTime>,
-);
+type JoinsoundEntry = (i32, Option<String>);
 
 pub async fn migrate_to_s3() {
     let connection = &mut database::connect();
 
     let results: Vec<JoinsoundEntry> = schema::joinsounds::table
-        .select(schema::joinsounds::all_columns)
+        .select((schema::joinsounds::id, schema::joinsounds::file_path))
         .load(connection)
         .expect("Failed to retrieve all joinsounds");
+    let library_paths = load_guild_and_library_paths(connection);
 
-    let pb = ProgressBar::new(results.len() as u64);
-    for (_id, discord_id, guild_id, file_path, _last_played) in results {
+    let pb = ProgressBar::new((results.len() + library_paths.len()) as u64);
+    for (id, file_path) in results {
         if let Some(path) = file_path {
             // Get file
             let file = fs::File::open(path.clone())
@@ -59,28 +54,58 @@ pub async fn migrate_to_s3() {
 
             // Save db entry with new sound path if applicable
             if path.starts_with("/") {
-                if let Some(guild) = guild_id {
-                    diesel::update(schema::joinsounds::table)
-                        .filter(schema::joinsounds::discord_id.eq(discord_id))
-                        .filter(schema::joinsounds::guild_id.eq(guild))
-                        .set(schema::joinsounds::file_path.eq(new_path))
-                        .execute(connection)
-                        .expect("Error setting new path");
-                } else {
-                    diesel::update(schema::joinsounds::table)
-                        .filter(schema::joinsounds::discord_id.eq(discord_id))
-                        .filter(schema::joinsounds::guild_id.is_null())
-                        .set(schema::joinsounds::file_path.eq(new_path))
-                        .execute(connection)
-                        .expect("Error setting new path");
-                }
+                diesel::update(schema::joinsounds::table.find(id))
+                    .set(schema::joinsounds::file_path.eq(new_path))
+                    .execute(connection)
+                    .expect("Error setting new path");
             }
         }
         pb.inc(1);
     }
+    // Library and guild sounds are always stored with relative paths
+    for path in library_paths {
+        if let Ok(file) = fs::File::open(&path).await {
+            let _ = file::save_file_on_s3(PathBuf::from(path), file).await;
+        }
+        pb.inc(1);
+    }
     pb.finish_with_message("Done!");
 }
 
+fn load_guild_and_library_paths(connection: &mut MysqlConnection) -> Vec<String> {
+    let mut paths: Vec<String> = schema::library_sounds::table
+        .select(schema::library_sounds::file_path)
+        .load(connection)
+        .expect("Failed to retrieve all library sounds");
+    paths.extend(
+        schema::channel_themes::table
+            .select(schema::channel_themes::file_path)
+            .load::<String>(connection)
+            .expect("Failed to retrieve all channel themes"),
+    );
+    paths.extend(
+        schema::guild_default_sounds::table
+            .select(schema::guild_default_sounds::file_path)
+            .load::<String>(connection)
+            .expect("Failed to retrieve all default sounds"),
+    );
+    paths.extend(
+        schema::guild_settings::table
+            .select(schema::guild_settings::group_sound_path)
+            .load::<Option<String>>(connection)
+            .expect("Failed to retrieve all group sounds")
+            .into_iter()
+            .flatten(),
+    );
+    paths.extend(
+        schema::soundboard_sounds::table
+            .select(schema::soundboard_sounds::file_path)
+            .load::<String>(connection)
+            .expect("Failed to retrieve all soundboard sounds"),
+    );
+    paths
+}
+
 pub async fn migrate_to_file_system() {
     let bucket_name = env::var("S3_BUCKET_NAME")
         .unwrap_or(String::from("join-sound-johnson"))
@@ -113,30 +138,34 @@ pub async fn migrate_to_file_system() {
     let connection = &mut database::connect();
 
     let results: Vec<JoinsoundEntry> = schema::joinsounds::table
-        .select(schema::joinsounds::all_columns)
+        .select((schema::joinsounds::id, schema::joinsounds::file_path))
         .load(connection)
         .expect("Failed to retrieve all joinsounds");
 
-    let pb = ProgressBar::new(results.len() as u64);
-    for (_id, _discord_id, _guild_id, file_path, _last_played) in results {
-        if let Some(path) = file_path {
-            if let Ok(response) = bucket.get_object(&path).await {
-                create_dir_all(Path::new(&path).parent().unwrap())
-                    .await
-                    .expect("Failed to make the directory");
-                let final_file_path = Path::new(&path);
-                let mut file = OpenOptions::new()
-                    .read(true)
-                    .write(true)
-                    .create(true)
-                    .truncate(true)
-                    .open(final_file_path)
-                    .await
-                    .expect("Failed to create file");
-                file.write_all(response.bytes())
-                    .await
-                    .expect("Failed to write the file");
-            }
+    let paths = results
+        .into_iter()
+        .filter_map(|(_id, file_path)| file_path)
+        .chain(load_guild_and_library_paths(connection))
+        .collect::<Vec<_>>();
+
+    let pb = ProgressBar::new(paths.len() as u64);
+    for path in paths {
+        if let Ok(response) = bucket.get_object(&path).await {
+            create_dir_all(Path::new(&path).parent().unwrap())
+                .await
+                .expect("Failed to make the directory");
+            let final_file_path = Path::new(&path);
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(final_file_path)
+                .await
+                .expect("Failed to create file");
+            file.write_all(response.bytes())
+                .await
+                .expect("Failed to write the file");
         }
         pb.inc(1);
     }